@@ -0,0 +1,463 @@
+//! Local copy of the Kodik catalog in `SQLite`.
+//!
+//! [`Catalog::sync`] pages through the `list` endpoint, newest update first,
+//! and stores every material with its episodes and material data. Later
+//! syncs stop at the first material not updated since the previous one.
+//! [`Catalog::search`] and [`Catalog::find`] then answer without the API.
+use std::{
+    fmt::Write as _,
+    path::Path,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use kodik_utils::Error;
+use rusqlite::{
+    Connection, OptionalExtension, Transaction, params, params_from_iter, types::Value,
+};
+
+use crate::{
+    client::KodikApi,
+    id::ExternalId,
+    request::{ListRequest, ListSort, Order},
+    types::{Material, MaterialType, TranslationType},
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS materials (
+        id TEXT PRIMARY KEY,
+        type TEXT NOT NULL,
+        search_text TEXT NOT NULL,
+        year INTEGER,
+        translation_id INTEGER NOT NULL,
+        translation_title TEXT NOT NULL,
+        translation_type TEXT NOT NULL,
+        genres TEXT NOT NULL,
+        kinopoisk_id TEXT,
+        imdb_id TEXT,
+        mdl_id TEXT,
+        shikimori_id TEXT,
+        worldart_link TEXT,
+        updated_at TEXT,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS materials_kinopoisk_id ON materials (kinopoisk_id);
+    CREATE INDEX IF NOT EXISTS materials_imdb_id ON materials (imdb_id);
+    CREATE INDEX IF NOT EXISTS materials_mdl_id ON materials (mdl_id);
+    CREATE INDEX IF NOT EXISTS materials_shikimori_id ON materials (shikimori_id);
+    CREATE INDEX IF NOT EXISTS materials_worldart_link ON materials (worldart_link);
+    CREATE INDEX IF NOT EXISTS materials_updated_at ON materials (updated_at);
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+";
+
+/// Key of the `updated_at` of the newest material seen by the last sync.
+const SYNCED_UNTIL: &str = "synced_until";
+
+/// Key of the local time of the last sync, in seconds since the Unix epoch.
+const SYNCED_AT: &str = "synced_at";
+
+/// Materials requested per `list` page, the most Kodik allows.
+const PAGE_LIMIT: u32 = 100;
+
+/// `SQLite` database of materials.
+#[derive(Debug)]
+pub struct Catalog {
+    conn: Mutex<Connection>,
+}
+
+/// Outcome of [`Catalog::sync`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SyncStats {
+    /// Pages fetched.
+    pub pages: u32,
+    /// Materials added or updated.
+    pub materials: u64,
+}
+
+/// Query of [`Catalog::search`]. Empty fields match everything, lists match
+/// any of their values.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CatalogQuery {
+    /// Part of any title, in any case.
+    pub title: Option<String>,
+    /// Release years.
+    pub years: Vec<u16>,
+    /// Material types.
+    pub types: Vec<MaterialType>,
+    /// Genres from any source, in any case.
+    pub genres: Vec<String>,
+    /// Part of the translation title, in any case.
+    pub translation_title: Option<String>,
+    /// Voice-overs or subtitles only.
+    pub translation_type: Option<TranslationType>,
+    /// Id on another site.
+    pub id: Option<ExternalId>,
+    /// Maximum number of results.
+    pub limit: Option<u32>,
+}
+
+impl Catalog {
+    /// Opens the catalog at `path`, creating it and its directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Catalog` if the database cannot be opened.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(Error::catalog)?;
+        }
+        Self::new(Connection::open(path).map_err(Error::catalog)?)
+    }
+
+    /// Opens an empty catalog kept in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Catalog` if the database cannot be created.
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::new(Connection::open_in_memory().map_err(Error::catalog)?)
+    }
+
+    fn new(conn: Connection) -> Result<Self, Error> {
+        conn.execute_batch(SCHEMA).map_err(Error::catalog)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Fetches the materials updated since the last sync, or every material
+    /// on the first one, with their episodes and material data.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`KodikApi::list`] and `Error::Catalog` if the
+    /// database cannot be written. Materials stored before the error are
+    /// kept, the next sync fetches them again.
+    pub async fn sync(&self, api: &KodikApi) -> Result<SyncStats, Error> {
+        let since = self.synced_until()?;
+        let request = ListRequest {
+            limit: Some(PAGE_LIMIT),
+            sort: Some(ListSort::UpdatedAt),
+            order: Some(Order::Desc),
+            with_episodes: true,
+            with_material_data: true,
+            ..ListRequest::default()
+        };
+
+        let mut stats = SyncStats::default();
+        let mut newest = None;
+        let mut page = api.list(&request).await?;
+        loop {
+            stats.pages += 1;
+            let (stored, done) = self.store(&page.results, since.as_deref())?;
+            stats.materials += stored;
+            if newest.is_none() {
+                newest = page.results.first().and_then(|m| m.updated_at.clone());
+            }
+            log::info!(
+                "Catalog: {} materials stored, page {}",
+                stats.materials,
+                stats.pages
+            );
+
+            if done {
+                break;
+            }
+            match api.next_page(&page).await? {
+                Some(next) => page = next,
+                None => break,
+            }
+        }
+
+        self.mark_synced(newest.as_deref())?;
+        Ok(stats)
+    }
+
+    /// Records a sync finished just now, `newest` being the `updated_at` of
+    /// the newest material it saw, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Catalog` if the database cannot be written.
+    pub fn mark_synced(&self, newest: Option<&str>) -> Result<(), Error> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            .to_string();
+        let mut conn = self.lock();
+        let tx = conn.transaction().map_err(Error::catalog)?;
+        if let Some(newest) = newest {
+            set_meta(&tx, SYNCED_UNTIL, newest)?;
+        }
+        set_meta(&tx, SYNCED_AT, &now)?;
+        tx.commit().map_err(Error::catalog)?;
+        drop(conn);
+        Ok(())
+    }
+
+    /// Stores `materials`, sorted by descending `updated_at`, up to the first
+    /// one updated before `since`. Returns how many were stored and whether
+    /// such a material was reached.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Catalog` if the database cannot be written.
+    pub fn store(&self, materials: &[Material], since: Option<&str>) -> Result<(u64, bool), Error> {
+        let mut conn = self.lock();
+        let tx = conn.transaction().map_err(Error::catalog)?;
+        let mut stored = 0;
+        let mut done = false;
+        for material in materials {
+            if let (Some(since), Some(updated_at)) = (since, material.updated_at.as_deref())
+                && updated_at < since
+            {
+                done = true;
+                break;
+            }
+            upsert(&tx, material)?;
+            stored += 1;
+        }
+        tx.commit().map_err(Error::catalog)?;
+        drop(conn);
+        Ok((stored, done))
+    }
+
+    /// Returns the `updated_at` of the newest material of the last sync, or
+    /// `None` if the catalog was never synced.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Catalog` if the database cannot be read.
+    pub fn synced_until(&self) -> Result<Option<String>, Error> {
+        self.lock()
+            .query_row(
+                "SELECT value FROM meta WHERE key = ?1",
+                [SYNCED_UNTIL],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(Error::catalog)
+    }
+
+    /// Returns when the catalog was last synced, or `None` if it never was.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Catalog` if the database cannot be read.
+    pub fn synced_at(&self) -> Result<Option<SystemTime>, Error> {
+        let secs: Option<String> = self
+            .lock()
+            .query_row(
+                "SELECT value FROM meta WHERE key = ?1",
+                [SYNCED_AT],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(Error::catalog)?;
+        Ok(secs
+            .and_then(|secs| secs.parse().ok())
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)))
+    }
+
+    /// Returns the number of materials stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Catalog` if the database cannot be read.
+    pub fn len(&self) -> Result<u64, Error> {
+        self.lock()
+            .query_row("SELECT COUNT(*) FROM materials", [], |row| row.get(0))
+            .map_err(Error::catalog)
+    }
+
+    /// Returns `true` if no material is stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Catalog` if the database cannot be read.
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Returns the materials with id `id` on another site, one per
+    /// translation, like [`KodikApi::lookup`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Catalog` if the database cannot be read.
+    pub fn find(&self, id: &ExternalId) -> Result<Vec<Material>, Error> {
+        self.search(&CatalogQuery {
+            id: Some(id.clone()),
+            ..CatalogQuery::default()
+        })
+    }
+
+    /// Returns the materials matching `query`, most recently updated first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Catalog` if the database cannot be read.
+    pub fn search(&self, query: &CatalogQuery) -> Result<Vec<Material>, Error> {
+        let mut sql = "SELECT data FROM materials WHERE 1 = 1".to_owned();
+        let mut args: Vec<Value> = Vec::new();
+
+        // Text columns are stored lowercased by Rust, `SQLite` only folds ASCII.
+        if let Some(title) = &query.title {
+            sql.push_str(" AND search_text LIKE ? ESCAPE '\\'");
+            args.push(contains(title));
+        }
+        any_of(
+            &mut sql,
+            &mut args,
+            "year = ?",
+            query.years.iter().map(|&y| Value::Integer(y.into())),
+        );
+        any_of(
+            &mut sql,
+            &mut args,
+            "type = ?",
+            query
+                .types
+                .iter()
+                .map(|t| Value::Text(t.as_str().to_owned())),
+        );
+        any_of(
+            &mut sql,
+            &mut args,
+            "genres LIKE ? ESCAPE '\\'",
+            query
+                .genres
+                .iter()
+                .map(|genre| contains(&format!("|{genre}|"))),
+        );
+        if let Some(title) = &query.translation_title {
+            sql.push_str(" AND translation_title LIKE ? ESCAPE '\\'");
+            args.push(contains(title));
+        }
+        if let Some(r#type) = query.translation_type {
+            sql.push_str(" AND translation_type = ?");
+            args.push(Value::Text(r#type.as_str().to_owned()));
+        }
+        if let Some(id) = &query.id {
+            let (column, value) = id.param();
+            let _ = write!(sql, " AND {column} = ?");
+            args.push(Value::Text(value.to_owned()));
+        }
+        sql.push_str(" ORDER BY updated_at DESC");
+        if let Some(limit) = query.limit {
+            let _ = write!(sql, " LIMIT {limit}");
+        }
+
+        let conn = self.lock();
+        let mut stmt = conn.prepare(&sql).map_err(Error::catalog)?;
+        let rows = stmt
+            .query_map(params_from_iter(args), |row| row.get::<_, String>(0))
+            .and_then(Iterator::collect::<Result<Vec<_>, _>>)
+            .map_err(Error::catalog)?;
+        drop(stmt);
+        drop(conn);
+        rows.iter()
+            .map(|data| serde_json::from_str(data).map_err(Error::catalog))
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn set_meta(tx: &Transaction<'_>, key: &str, value: &str) -> Result<(), Error> {
+    tx.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    )
+    .map_err(Error::catalog)?;
+    Ok(())
+}
+
+fn upsert(tx: &Transaction<'_>, material: &Material) -> Result<(), Error> {
+    let data = serde_json::to_string(material).map_err(Error::catalog)?;
+    let search_text = [
+        Some(material.title.as_str()),
+        material.title_orig.as_deref(),
+        material.other_title.as_deref(),
+    ]
+    .into_iter()
+    .chain(material.material_data.iter().flat_map(|data| {
+        [data.title_en.as_deref(), data.anime_title.as_deref()]
+            .into_iter()
+            .chain(data.other_titles.iter().map(|t| Some(t.as_str())))
+            .chain(data.other_titles_en.iter().map(|t| Some(t.as_str())))
+    }))
+    .flatten()
+    .collect::<Vec<_>>()
+    .join("\n")
+    .to_lowercase();
+    let genres = material
+        .material_data
+        .iter()
+        .flat_map(|data| {
+            data.all_genres
+                .iter()
+                .chain(&data.genres)
+                .chain(&data.anime_genres)
+        })
+        .fold(String::new(), |mut genres, genre| {
+            let _ = write!(genres, "|{}|", genre.to_lowercase());
+            genres
+        });
+
+    tx.execute(
+        "INSERT OR REPLACE INTO materials (
+            id, type, search_text, year, translation_id, translation_title, translation_type,
+            genres, kinopoisk_id, imdb_id, mdl_id, shikimori_id, worldart_link, updated_at, data
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+        params![
+            material.id,
+            material.r#type.as_str(),
+            search_text,
+            material.year,
+            material.translation.id,
+            material.translation.title.to_lowercase(),
+            material.translation.r#type.as_str(),
+            genres,
+            material.kinopoisk_id,
+            material.imdb_id,
+            material.mdl_id,
+            material.shikimori_id,
+            material.worldart_link,
+            material.updated_at,
+            data,
+        ],
+    )
+    .map_err(Error::catalog)?;
+    Ok(())
+}
+
+/// Appends `(condition OR condition ...)` with one condition per value.
+fn any_of(
+    sql: &mut String,
+    args: &mut Vec<Value>,
+    condition: &str,
+    values: impl ExactSizeIterator<Item = Value>,
+) {
+    if values.len() == 0 {
+        return;
+    }
+    let _ = write!(sql, " AND ({})", vec![condition; values.len()].join(" OR "));
+    args.extend(values);
+}
+
+/// Returns a `LIKE` pattern matching `text` anywhere, in any case.
+fn contains(text: &str) -> Value {
+    let escaped = text
+        .to_lowercase()
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    Value::Text(format!("%{escaped}%"))
+}
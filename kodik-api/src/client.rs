@@ -0,0 +1,227 @@
+//! Client of the Kodik API.
+use std::{fmt::Debug, iter};
+
+use kodik_utils::{Error, RATE_LIMITER, Stage, redact::Redacted};
+use reqwest::{
+    Client, Url,
+    header::{ACCEPT, HeaderValue},
+};
+use serde::{Deserialize, de::DeserializeOwned};
+
+use crate::{
+    id::ExternalId,
+    request::{AggregateRequest, GenresRequest, ListRequest, SearchRequest},
+    types::{Count, Material, Page, TranslationCount, YearCount},
+};
+
+/// Base URL of the Kodik API.
+pub const BASE_URL: &str = "https://kodik-api.com";
+
+/// Client of the Kodik API, cheap to clone. Every request carries the API
+/// token given to [`KodikApi::new`].
+#[derive(Debug, Clone)]
+pub struct KodikApi {
+    client: Client,
+    token: String,
+    base_url: String,
+}
+
+/// Body of a response: the results, or the error Kodik reports instead.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Reply<T> {
+    Error { error: String },
+    Ok(T),
+}
+
+impl KodikApi {
+    /// Creates a client sending requests with `client` and `token`.
+    #[must_use]
+    pub fn new(client: Client, token: impl Into<String>) -> Self {
+        Self {
+            client,
+            token: token.into(),
+            base_url: BASE_URL.to_owned(),
+        }
+    }
+
+    /// Sends requests to `base_url` instead of [`BASE_URL`], e.g. a mirror.
+    #[must_use]
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        base_url
+            .into()
+            .trim_end_matches('/')
+            .clone_into(&mut self.base_url);
+        self
+    }
+
+    /// Searches materials.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Request` if the request fails,
+    /// `Error::UnexpectedResponse` if Kodik reports an error, such as a wrong
+    /// token, and `Error::Decode` if the response cannot be parsed.
+    pub async fn search(&self, request: &SearchRequest) -> Result<Page<Material>, Error> {
+        self.get("search", request.params()).await
+    }
+
+    /// Looks materials up by their id on another site, one per translation,
+    /// with their seasons and episodes.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::search`].
+    pub async fn lookup(&self, id: &ExternalId) -> Result<Page<Material>, Error> {
+        let request = SearchRequest {
+            with_seasons: true,
+            with_episodes: true,
+            ..SearchRequest::external_id(id)
+        };
+        self.search(&request).await
+    }
+
+    /// Lists materials, newest first unless sorted otherwise. Use
+    /// [`Self::next_page`] for the following pages.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::search`].
+    pub async fn list(&self, request: &ListRequest) -> Result<Page<Material>, Error> {
+        self.get("list", request.params()).await
+    }
+
+    /// Counts materials by translation.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::search`].
+    pub async fn translations(
+        &self,
+        request: &AggregateRequest,
+    ) -> Result<Page<TranslationCount>, Error> {
+        self.get("translations/v2", request.params()).await
+    }
+
+    /// Counts materials by genre.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::search`].
+    pub async fn genres(&self, request: &GenresRequest) -> Result<Page<Count>, Error> {
+        self.get("genres", request.params()).await
+    }
+
+    /// Counts materials by country.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::search`].
+    pub async fn countries(&self, request: &AggregateRequest) -> Result<Page<Count>, Error> {
+        self.get("countries", request.params()).await
+    }
+
+    /// Counts materials by release year.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::search`].
+    pub async fn years(&self, request: &AggregateRequest) -> Result<Page<YearCount>, Error> {
+        self.get("years", request.params()).await
+    }
+
+    /// Counts materials by video quality.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::search`].
+    pub async fn qualities(&self, request: &AggregateRequest) -> Result<Page<Count>, Error> {
+        self.get("qualities/v2", request.params()).await
+    }
+
+    /// Fetches the page after `page`, or returns `None` on the last page.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::search`].
+    pub async fn next_page<T: DeserializeOwned + Debug + Sync>(
+        &self,
+        page: &Page<T>,
+    ) -> Result<Option<Page<T>>, Error> {
+        match page.next_page.as_deref() {
+            Some(url) => self.fetch(url).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the URL of `endpoint` with the token and `params`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidUrl` if the base URL is malformed.
+    pub fn url(&self, endpoint: &str, params: Vec<(&'static str, String)>) -> Result<Url, Error> {
+        let base = format!("{}/{endpoint}", self.base_url);
+        Url::parse_with_params(
+            &base,
+            iter::once(("token", self.token.clone())).chain(params),
+        )
+        .map_err(|e| Error::InvalidUrl {
+            url: base.clone(),
+            reason: e.to_string(),
+        })
+    }
+
+    async fn get<T: DeserializeOwned + Debug>(
+        &self,
+        endpoint: &str,
+        params: Vec<(&'static str, String)>,
+    ) -> Result<T, Error> {
+        let url = self.url(endpoint, params)?;
+        self.fetch(url.as_str()).await
+    }
+
+    async fn fetch<T: DeserializeOwned + Debug>(&self, url: &str) -> Result<T, Error> {
+        let profile = kodik_utils::session_headers()?;
+
+        log::info!("GET to {}...", Redacted(url));
+
+        let build = || {
+            self.client
+                .get(url)
+                .headers(profile.clone())
+                .header(ACCEPT, HeaderValue::from_static("application/json"))
+        };
+        let resp = RATE_LIMITER.send(Stage::Api, url, build, |_, _| {}).await?;
+
+        let status = resp.status();
+        let body = resp
+            .bytes()
+            .await
+            .map_err(Error::request(Stage::Api, url))?;
+
+        // Kodik answers a wrong token or parameter with `{"error": "..."}`,
+        // sometimes with an error status.
+        match serde_json::from_slice::<Reply<T>>(&body) {
+            Ok(Reply::Error { error }) => Err(Error::unexpected(Stage::Api, url, error)),
+            Ok(Reply::Ok(resp)) if status.is_success() => {
+                log::trace!(
+                    "Fetched to {}, response: {}",
+                    Redacted(url),
+                    Redacted(format_args!("{resp:#?}"))
+                );
+                Ok(resp)
+            }
+            Ok(Reply::Ok(_)) => Err(Error::unexpected(
+                Stage::Api,
+                url,
+                format!("status {status}"),
+            )),
+            Err(_) if !status.is_success() => Err(Error::unexpected(
+                Stage::Api,
+                url,
+                format!("status {status}"),
+            )),
+            Err(e) => Err(Error::decode(Stage::Api, url)(e)),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Ids of a material on other sites, which the `search` endpoint can look
+//! materials up by.
+use std::{fmt, str::FromStr};
+
+use kodik_utils::Error;
+
+use crate::types::Material;
+
+/// Id of a material on Kinopoisk, IMDb, MyDramaList, Shikimori or World Art.
+///
+/// Parses from and displays as `prefix:id`:
+///
+/// - `kp:326` or `kinopoisk:326` for Kinopoisk,
+/// - `imdb:tt0111161` for IMDb,
+/// - `mdl:18452` for MyDramaList,
+/// - `shiki:43` or `mal:43` for Shikimori, which shares its ids with
+///   MyAnimeList,
+/// - `worldart:<link>` for World Art, by the link of its page.
+///
+/// ```
+/// use kodik_api::ExternalId;
+///
+/// let id: ExternalId = "imdb:tt0111161".parse().unwrap();
+/// assert_eq!(ExternalId::Imdb("tt0111161".to_owned()), id);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ExternalId {
+    /// Kinopoisk id.
+    Kinopoisk(String),
+    /// IMDb id, e.g. `tt0111161`.
+    Imdb(String),
+    /// MyDramaList id.
+    MyDramaList(String),
+    /// Shikimori id, the same as the MyAnimeList one.
+    Shikimori(String),
+    /// Link to a World Art page.
+    WorldArt(String),
+}
+
+impl ExternalId {
+    /// Returns the name of the `search` parameter and its value.
+    #[must_use]
+    pub fn param(&self) -> (&'static str, &str) {
+        match self {
+            Self::Kinopoisk(id) => ("kinopoisk_id", id),
+            Self::Imdb(id) => ("imdb_id", id),
+            Self::MyDramaList(id) => ("mdl_id", id),
+            Self::Shikimori(id) => ("shikimori_id", id),
+            Self::WorldArt(link) => ("worldart_link", link),
+        }
+    }
+
+    /// Returns `true` if `material` has this id.
+    #[must_use]
+    pub fn matches(&self, material: &Material) -> bool {
+        let id = match self {
+            Self::Kinopoisk(_) => &material.kinopoisk_id,
+            Self::Imdb(_) => &material.imdb_id,
+            Self::MyDramaList(_) => &material.mdl_id,
+            Self::Shikimori(_) => &material.shikimori_id,
+            Self::WorldArt(_) => &material.worldart_link,
+        };
+        id.as_deref() == Some(self.param().1)
+    }
+
+    const fn prefix(&self) -> &'static str {
+        match self {
+            Self::Kinopoisk(_) => "kp",
+            Self::Imdb(_) => "imdb",
+            Self::MyDramaList(_) => "mdl",
+            Self::Shikimori(_) => "shiki",
+            Self::WorldArt(_) => "worldart",
+        }
+    }
+}
+
+impl fmt::Display for ExternalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.prefix(), self.param().1)
+    }
+}
+
+impl FromStr for ExternalId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| Error::InvalidUrl {
+            url: s.to_owned(),
+            reason: reason.to_owned(),
+        };
+
+        let (prefix, id) = s
+            .split_once(':')
+            .ok_or_else(|| invalid("expected an id like kp:326 or imdb:tt0111161"))?;
+        let id = id.trim();
+        if id.is_empty() {
+            return Err(invalid("empty id"));
+        }
+
+        let is_number = || id.bytes().all(|b| b.is_ascii_digit());
+        match prefix.to_ascii_lowercase().as_str() {
+            "kp" | "kinopoisk" if is_number() => Ok(Self::Kinopoisk(id.to_owned())),
+            "imdb"
+                if id.len() > 2
+                    && id.starts_with("tt")
+                    && id.bytes().skip(2).all(|b| b.is_ascii_digit()) =>
+            {
+                Ok(Self::Imdb(id.to_owned()))
+            }
+            "mdl" if is_number() => Ok(Self::MyDramaList(id.to_owned())),
+            "shiki" | "shikimori" | "mal" if is_number() => Ok(Self::Shikimori(id.to_owned())),
+            "worldart" if id.contains("world-art.ru") => Ok(Self::WorldArt(id.to_owned())),
+            "kp" | "kinopoisk" | "mdl" | "shiki" | "shikimori" | "mal" => {
+                Err(invalid("expected a numeric id"))
+            }
+            "imdb" => Err(invalid("expected an IMDb id like tt0111161")),
+            "worldart" => Err(invalid("expected a link to a World Art page")),
+            _ => Err(invalid("unknown id prefix")),
+        }
+    }
+}
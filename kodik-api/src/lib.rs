@@ -0,0 +1,33 @@
+//! # Kodik API library.
+//! `kodik-api` is a typed client for the public Kodik API at
+//! `kodik-api.com`: searching and listing materials, and the `translations`,
+//! `genres`, `countries`, `years` and `qualities` aggregations.
+//!
+//! Requests go through the shared rate limiter of `kodik_utils`, like every
+//! other request of the Kodik crates.
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used, clippy::indexing_slicing)]
+mod tests;
+
+#[cfg(feature = "catalog")]
+pub mod catalog;
+pub mod client;
+pub mod id;
+pub mod request;
+pub mod types;
+pub mod watch;
+
+pub use client::{BASE_URL, KodikApi};
+pub use id::ExternalId;
+pub use request::{
+    AggregateRequest, AggregateSort, Filters, GenresRequest, GenresType, ListRequest, ListSort,
+    Order, SearchRequest,
+};
+pub use types::{
+    AnimeKind, AnimeStatus, Count, Episode, Material, MaterialData, MaterialType, Page, Season,
+    Translation, TranslationCount, TranslationType, YearCount,
+};
+pub use watch::{NewEpisode, Position, Track, WatchState};
+
+pub extern crate reqwest;
@@ -0,0 +1,460 @@
+//! Typed requests of the Kodik API.
+//!
+//! Requests are plain structs, fill in the fields you need and leave the
+//! rest to [`Default`]:
+//!
+//! ```
+//! use kodik_api::{Filters, MaterialType, SearchRequest};
+//!
+//! let request = SearchRequest {
+//!     with_episodes: true,
+//!     filters: Filters {
+//!         types: vec![MaterialType::AnimeSerial],
+//!         ..Filters::default()
+//!     },
+//!     ..SearchRequest::title("Naruto")
+//! };
+//! assert!(request.params().contains(&("types", "anime-serial".to_owned())));
+//! ```
+use crate::{
+    id::ExternalId,
+    types::{AnimeKind, AnimeStatus, MaterialType, TranslationType},
+};
+
+/// Filters shared by every endpoint. Lists match any of their values.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Filters {
+    /// Material types.
+    pub types: Vec<MaterialType>,
+    /// Release years.
+    pub year: Vec<u16>,
+    /// `Some(false)` hides camera recordings, `Some(true)` keeps only them.
+    pub camrip: Option<bool>,
+    /// `Some(false)` hides materials with LGBT scenes.
+    pub lgbt: Option<bool>,
+    /// Translation ids.
+    pub translation_id: Vec<u32>,
+    /// Voice-overs or subtitles only.
+    pub translation_type: Option<TranslationType>,
+    /// Keeps materials that have all of these fields, e.g. `shikimori_id`.
+    pub has_field: Vec<String>,
+    /// Anime kinds.
+    pub anime_kind: Vec<AnimeKind>,
+    /// Anime statuses.
+    pub anime_status: Vec<AnimeStatus>,
+    /// MyDramaList tags.
+    pub mydramalist_tags: Vec<String>,
+    /// MPAA ratings, e.g. `pg_13`.
+    pub rating_mpaa: Vec<String>,
+    /// Minimal viewer age, a number or a range such as `12-16`.
+    pub minimal_age: Option<String>,
+    /// Kinopoisk rating, a number or a range such as `7-10`.
+    pub kinopoisk_rating: Option<String>,
+    /// IMDb rating, a number or a range such as `7-10`.
+    pub imdb_rating: Option<String>,
+    /// Shikimori rating, a number or a range such as `7-10`.
+    pub shikimori_rating: Option<String>,
+    /// MyDramaList rating, a number or a range such as `7-10`.
+    pub mydramalist_rating: Option<String>,
+    /// Duration in minutes, a number or a range such as `20-30`.
+    pub duration: Option<String>,
+    /// Kinopoisk genres.
+    pub genres: Vec<String>,
+    /// Shikimori genres.
+    pub anime_genres: Vec<String>,
+    /// MyDramaList genres.
+    pub drama_genres: Vec<String>,
+    /// Genres from any source.
+    pub all_genres: Vec<String>,
+    /// Countries of production.
+    pub countries: Vec<String>,
+    /// Shikimori studios.
+    pub anime_studios: Vec<String>,
+    /// Shikimori licensors.
+    pub anime_licensed_by: Vec<String>,
+    /// Actors.
+    pub actors: Vec<String>,
+    /// Directors.
+    pub directors: Vec<String>,
+    /// Hides materials blocked in any of these countries, e.g. `RU`.
+    pub not_blocked_in: Vec<String>,
+    /// Hides materials blocked in the country of the caller.
+    pub not_blocked_for_me: bool,
+}
+
+impl Filters {
+    fn write(&self, params: &mut Params) {
+        params.list("types", self.types.iter().map(|t| t.as_str()));
+        params.list("year", &self.year);
+        params.opt("camrip", self.camrip);
+        params.opt("lgbt", self.lgbt);
+        params.list("translation_id", &self.translation_id);
+        params.opt(
+            "translation_type",
+            self.translation_type.map(TranslationType::as_str),
+        );
+        params.list("has_field", &self.has_field);
+        params.list("anime_kind", self.anime_kind.iter().map(|k| k.as_str()));
+        params.list("anime_status", self.anime_status.iter().map(|s| s.as_str()));
+        params.list("mydramalist_tags", &self.mydramalist_tags);
+        params.list("rating_mpaa", &self.rating_mpaa);
+        params.opt("minimal_age", self.minimal_age.as_ref());
+        params.opt("kinopoisk_rating", self.kinopoisk_rating.as_ref());
+        params.opt("imdb_rating", self.imdb_rating.as_ref());
+        params.opt("shikimori_rating", self.shikimori_rating.as_ref());
+        params.opt("mydramalist_rating", self.mydramalist_rating.as_ref());
+        params.opt("duration", self.duration.as_ref());
+        params.list("genres", &self.genres);
+        params.list("anime_genres", &self.anime_genres);
+        params.list("drama_genres", &self.drama_genres);
+        params.list("all_genres", &self.all_genres);
+        params.list("countries", &self.countries);
+        params.list("anime_studios", &self.anime_studios);
+        params.list("anime_licensed_by", &self.anime_licensed_by);
+        params.list("actors", &self.actors);
+        params.list("directors", &self.directors);
+        params.list("not_blocked_in", &self.not_blocked_in);
+        params.flag("not_blocked_for_me", self.not_blocked_for_me);
+    }
+}
+
+/// Request of the `search` endpoint: materials by title or by an id on
+/// another site.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct SearchRequest {
+    /// Title in any language.
+    pub title: Option<String>,
+    /// Original title.
+    pub title_orig: Option<String>,
+    /// Matches the title exactly, ignoring case and punctuation.
+    pub strict: bool,
+    /// Matches the title exactly.
+    pub full_match: bool,
+    /// Kodik id, e.g. `serial-45534`.
+    pub id: Option<String>,
+    /// Player link.
+    pub player_link: Option<String>,
+    /// Kinopoisk id.
+    pub kinopoisk_id: Option<String>,
+    /// IMDb id, e.g. `tt0409591`.
+    pub imdb_id: Option<String>,
+    /// MyDramaList id.
+    pub mdl_id: Option<String>,
+    /// World Art animation id.
+    pub worldart_animation_id: Option<String>,
+    /// World Art cinema id.
+    pub worldart_cinema_id: Option<String>,
+    /// World Art page.
+    pub worldart_link: Option<String>,
+    /// Shikimori id.
+    pub shikimori_id: Option<String>,
+    /// Maximum number of results, at most 100.
+    pub limit: Option<u32>,
+    /// Translations listed first.
+    pub prioritize_translations: Vec<u32>,
+    /// Translations listed last.
+    pub unprioritize_translations: Vec<u32>,
+    /// Translation type listed first.
+    pub prioritize_translation_type: Option<TranslationType>,
+    /// Translations left out.
+    pub block_translations: Vec<u32>,
+    /// Only this season of serials.
+    pub season: Option<u32>,
+    /// Only this episode of serials.
+    pub episode: Option<u32>,
+    /// Adds [`Material::seasons`](crate::Material::seasons).
+    pub with_seasons: bool,
+    /// Adds the episode links to the seasons.
+    pub with_episodes: bool,
+    /// Adds the episode links, titles and screenshots to the seasons.
+    pub with_episodes_data: bool,
+    /// Links Kodik pages instead of players.
+    pub with_page_links: bool,
+    /// Adds [`Material::material_data`](crate::Material::material_data).
+    pub with_material_data: bool,
+    /// Filters applied to the results.
+    pub filters: Filters,
+}
+
+impl SearchRequest {
+    /// Searches by title.
+    #[must_use]
+    pub fn title(title: impl Into<String>) -> Self {
+        Self {
+            title: Some(title.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Searches by Shikimori id.
+    #[must_use]
+    pub fn shikimori_id(id: impl Into<String>) -> Self {
+        Self {
+            shikimori_id: Some(id.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Looks a material up by its id on another site.
+    #[must_use]
+    pub fn external_id(id: &ExternalId) -> Self {
+        let mut request = Self::default();
+        let field = match id {
+            ExternalId::Kinopoisk(_) => &mut request.kinopoisk_id,
+            ExternalId::Imdb(_) => &mut request.imdb_id,
+            ExternalId::MyDramaList(_) => &mut request.mdl_id,
+            ExternalId::Shikimori(_) => &mut request.shikimori_id,
+            ExternalId::WorldArt(_) => &mut request.worldart_link,
+        };
+        *field = Some(id.param().1.to_owned());
+        request
+    }
+
+    /// Returns the query parameters of the request, without the token.
+    #[must_use]
+    pub fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Params::default();
+        params.opt("title", self.title.as_ref());
+        params.opt("title_orig", self.title_orig.as_ref());
+        params.flag("strict", self.strict);
+        params.flag("full_match", self.full_match);
+        params.opt("id", self.id.as_ref());
+        params.opt("player_link", self.player_link.as_ref());
+        params.opt("kinopoisk_id", self.kinopoisk_id.as_ref());
+        params.opt("imdb_id", self.imdb_id.as_ref());
+        params.opt("mdl_id", self.mdl_id.as_ref());
+        params.opt("worldart_animation_id", self.worldart_animation_id.as_ref());
+        params.opt("worldart_cinema_id", self.worldart_cinema_id.as_ref());
+        params.opt("worldart_link", self.worldart_link.as_ref());
+        params.opt("shikimori_id", self.shikimori_id.as_ref());
+        params.opt("limit", self.limit);
+        params.list("prioritize_translations", &self.prioritize_translations);
+        params.list("unprioritize_translations", &self.unprioritize_translations);
+        params.opt(
+            "prioritize_translation_type",
+            self.prioritize_translation_type
+                .map(TranslationType::as_str),
+        );
+        params.list("block_translations", &self.block_translations);
+        params.opt("season", self.season);
+        params.opt("episode", self.episode);
+        params.flag("with_seasons", self.with_seasons);
+        params.flag("with_episodes", self.with_episodes);
+        params.flag("with_episodes_data", self.with_episodes_data);
+        params.flag("with_page_links", self.with_page_links);
+        params.flag("with_material_data", self.with_material_data);
+        self.filters.write(&mut params);
+        params.0
+    }
+}
+
+/// Field the `list` endpoint sorts by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ListSort {
+    /// Time of the last update, the default.
+    UpdatedAt,
+    /// Creation time.
+    CreatedAt,
+    /// Release year.
+    Year,
+    /// Kinopoisk rating.
+    KinopoiskRating,
+    /// IMDb rating.
+    ImdbRating,
+    /// Shikimori rating.
+    ShikimoriRating,
+}
+
+impl ListSort {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::UpdatedAt => "updated_at",
+            Self::CreatedAt => "created_at",
+            Self::Year => "year",
+            Self::KinopoiskRating => "kinopoisk_rating",
+            Self::ImdbRating => "imdb_rating",
+            Self::ShikimoriRating => "shikimori_rating",
+        }
+    }
+}
+
+/// Sort order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Order {
+    /// Ascending.
+    Asc,
+    /// Descending, the default.
+    Desc,
+}
+
+impl Order {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Asc => "asc",
+            Self::Desc => "desc",
+        }
+    }
+}
+
+/// Request of the `list` endpoint: every material, page by page.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ListRequest {
+    /// Results per page, at most 100.
+    pub limit: Option<u32>,
+    /// Field to sort by.
+    pub sort: Option<ListSort>,
+    /// Sort order.
+    pub order: Option<Order>,
+    /// Adds [`Material::seasons`](crate::Material::seasons).
+    pub with_seasons: bool,
+    /// Adds the episode links to the seasons.
+    pub with_episodes: bool,
+    /// Adds the episode links, titles and screenshots to the seasons.
+    pub with_episodes_data: bool,
+    /// Links Kodik pages instead of players.
+    pub with_page_links: bool,
+    /// Adds [`Material::material_data`](crate::Material::material_data).
+    pub with_material_data: bool,
+    /// Filters applied to the results.
+    pub filters: Filters,
+}
+
+impl ListRequest {
+    /// Returns the query parameters of the request, without the token.
+    #[must_use]
+    pub fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Params::default();
+        params.opt("limit", self.limit);
+        params.opt("sort", self.sort.map(ListSort::as_str));
+        params.opt("order", self.order.map(Order::as_str));
+        params.flag("with_seasons", self.with_seasons);
+        params.flag("with_episodes", self.with_episodes);
+        params.flag("with_episodes_data", self.with_episodes_data);
+        params.flag("with_page_links", self.with_page_links);
+        params.flag("with_material_data", self.with_material_data);
+        self.filters.write(&mut params);
+        params.0
+    }
+}
+
+/// Field the aggregation endpoints sort by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AggregateSort {
+    /// Title, for `translations`, `genres`, `countries` and `qualities`.
+    Title,
+    /// Year, for `years`.
+    Year,
+    /// Number of materials.
+    Count,
+}
+
+impl AggregateSort {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::Year => "year",
+            Self::Count => "count",
+        }
+    }
+}
+
+/// Request of the `translations`, `countries`, `years` and `qualities`
+/// endpoints, which count the materials matching the filters.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AggregateRequest {
+    /// Field to sort by.
+    pub sort: Option<AggregateSort>,
+    /// Sort order.
+    pub order: Option<Order>,
+    /// Filters applied to the counted materials.
+    pub filters: Filters,
+}
+
+impl AggregateRequest {
+    /// Returns the query parameters of the request, without the token.
+    #[must_use]
+    pub fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Params::default();
+        params.opt("sort", self.sort.map(AggregateSort::as_str));
+        params.opt("order", self.order.map(Order::as_str));
+        self.filters.write(&mut params);
+        params.0
+    }
+}
+
+/// Source of the genres returned by the `genres` endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GenresType {
+    /// Kinopoisk genres, the default.
+    Kinopoisk,
+    /// Shikimori genres.
+    Shikimori,
+    /// MyDramaList genres.
+    Mydramalist,
+    /// Genres from every source.
+    All,
+}
+
+impl GenresType {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Kinopoisk => "kinopoisk",
+            Self::Shikimori => "shikimori",
+            Self::Mydramalist => "mydramalist",
+            Self::All => "all",
+        }
+    }
+}
+
+/// Request of the `genres` endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GenresRequest {
+    /// Source of the genres.
+    pub genres_type: Option<GenresType>,
+    /// Sort, order and filters.
+    pub aggregate: AggregateRequest,
+}
+
+impl GenresRequest {
+    /// Returns the query parameters of the request, without the token.
+    #[must_use]
+    pub fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = self.aggregate.params();
+        if let Some(genres_type) = self.genres_type {
+            params.push(("genres_type", genres_type.as_str().to_owned()));
+        }
+        params
+    }
+}
+
+/// Query parameters, skipping unset values.
+#[derive(Default)]
+struct Params(Vec<(&'static str, String)>);
+
+impl Params {
+    fn opt(&mut self, name: &'static str, value: Option<impl ToString>) {
+        if let Some(value) = value {
+            self.0.push((name, value.to_string()));
+        }
+    }
+
+    fn flag(&mut self, name: &'static str, value: bool) {
+        if value {
+            self.0.push((name, "true".to_owned()));
+        }
+    }
+
+    // Kodik takes lists as comma separated values.
+    fn list<T: ToString>(&mut self, name: &'static str, values: impl IntoIterator<Item = T>) {
+        let joined = values
+            .into_iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        if !joined.is_empty() {
+            self.0.push((name, joined));
+        }
+    }
+}
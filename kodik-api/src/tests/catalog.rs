@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::{
+    ExternalId, Material, MaterialType, TranslationType,
+    catalog::{Catalog, CatalogQuery},
+};
+
+fn material(id: &str, title: &str, translation: &str, updated_at: &str) -> Material {
+    serde_json::from_value(json!({
+        "id": id,
+        "type": "anime-serial",
+        "link": format!("//kodik.info/serial/{id}/abc/720p"),
+        "title": title,
+        "title_orig": "Naruto",
+        "translation": {"id": 610, "title": translation, "type": "voice"},
+        "year": 2002,
+        "shikimori_id": "20",
+        "imdb_id": "tt0409591",
+        "updated_at": updated_at,
+        "material_data": {"anime_genres": ["Сёнэн", "Экшен"]}
+    }))
+    .unwrap()
+}
+
+fn catalog() -> Catalog {
+    let catalog = Catalog::open_in_memory().unwrap();
+    let materials = [
+        material("serial-2", "Наруто", "AniLibria.TV", "2024-02-01T00:00:00Z"),
+        material("serial-1", "Наруто", "Субтитры", "2024-01-01T00:00:00Z"),
+    ];
+    assert_eq!((2, false), catalog.store(&materials, None).unwrap());
+    catalog
+}
+
+#[test]
+fn store_test() {
+    let catalog = catalog();
+    assert_eq!(2, catalog.len().unwrap());
+
+    // Stored again with a newer update, the older one is where the last sync ended.
+    let materials = [
+        material("serial-1", "Наруто", "Субтитры", "2024-03-01T00:00:00Z"),
+        material("serial-2", "Наруто", "AniLibria.TV", "2024-02-01T00:00:00Z"),
+    ];
+    let stored = catalog
+        .store(&materials, Some("2024-02-15T00:00:00Z"))
+        .unwrap();
+    assert_eq!((1, true), stored);
+    assert_eq!(2, catalog.len().unwrap());
+    assert_eq!(None, catalog.synced_until().unwrap());
+}
+
+#[test]
+fn mark_synced_test() {
+    let catalog = catalog();
+    assert_eq!(None, catalog.synced_at().unwrap());
+
+    catalog.mark_synced(Some("2024-03-01T00:00:00Z")).unwrap();
+    catalog.mark_synced(None).unwrap();
+
+    assert_eq!(
+        Some("2024-03-01T00:00:00Z"),
+        catalog.synced_until().unwrap().as_deref()
+    );
+    let synced_at = catalog.synced_at().unwrap().unwrap();
+    assert!(synced_at.elapsed().unwrap() < Duration::from_mins(1));
+}
+
+#[test]
+fn search_test() {
+    let catalog = catalog();
+    let search = |query: CatalogQuery| {
+        catalog
+            .search(&query)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        vec!["serial-2", "serial-1"],
+        search(CatalogQuery::default())
+    );
+    let query = CatalogQuery {
+        title: Some("наРУ".to_owned()),
+        ..CatalogQuery::default()
+    };
+    assert_eq!(vec!["serial-2", "serial-1"], search(query));
+    let query = CatalogQuery {
+        title: Some("naruto".to_owned()),
+        translation_title: Some("aniLIBRIA".to_owned()),
+        ..CatalogQuery::default()
+    };
+    assert_eq!(vec!["serial-2"], search(query));
+    let query = CatalogQuery {
+        genres: vec!["драма".to_owned(), "сёнэн".to_owned()],
+        years: vec![2001, 2002],
+        types: vec![MaterialType::AnimeSerial],
+        translation_type: Some(TranslationType::Voice),
+        limit: Some(1),
+        ..CatalogQuery::default()
+    };
+    assert_eq!(vec!["serial-2"], search(query));
+    let query = CatalogQuery {
+        title: Some("100%".to_owned()),
+        ..CatalogQuery::default()
+    };
+    assert!(search(query).is_empty());
+    let query = CatalogQuery {
+        genres: vec!["драма".to_owned()],
+        ..CatalogQuery::default()
+    };
+    assert!(search(query).is_empty());
+}
+
+#[test]
+fn find_test() {
+    let catalog = catalog();
+    let found = catalog
+        .find(&ExternalId::Shikimori("20".to_owned()))
+        .unwrap();
+    assert_eq!(2, found.len());
+    assert_eq!(
+        material("serial-2", "Наруто", "AniLibria.TV", "2024-02-01T00:00:00Z"),
+        found[0]
+    );
+    assert_eq!(
+        2,
+        catalog
+            .find(&ExternalId::Imdb("tt0409591".to_owned()))
+            .unwrap()
+            .len()
+    );
+    assert!(
+        catalog
+            .find(&ExternalId::Shikimori("21".to_owned()))
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,29 @@
+use reqwest::Client;
+
+use crate::{KodikApi, SearchRequest};
+
+#[test]
+fn url_test() {
+    let api = KodikApi::new(Client::new(), "secret").with_base_url("http://127.0.0.1:8080/");
+    let url = api
+        .url("search", SearchRequest::title("Наруто").params())
+        .unwrap();
+    assert_eq!(
+        "http://127.0.0.1:8080/search?token=secret&title=%D0%9D%D0%B0%D1%80%D1%83%D1%82%D0%BE",
+        url.as_str()
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires network access"]
+async fn search_test() {
+    let token = std::env::var("KODIK_TOKEN").unwrap();
+    let api = KodikApi::new(Client::new(), token);
+    let request = SearchRequest {
+        with_episodes: true,
+        ..SearchRequest::shikimori_id("20")
+    };
+
+    let page = api.search(&request).await.unwrap();
+    assert!(!page.results.is_empty());
+}
@@ -0,0 +1,46 @@
+use crate::{ExternalId, SearchRequest};
+
+#[test]
+fn parse_external_ids() {
+    let cases = [
+        ("kp:326", ExternalId::Kinopoisk("326".to_owned())),
+        ("Kinopoisk:326", ExternalId::Kinopoisk("326".to_owned())),
+        ("imdb:tt0111161", ExternalId::Imdb("tt0111161".to_owned())),
+        ("mdl:18452", ExternalId::MyDramaList("18452".to_owned())),
+        ("shiki:43", ExternalId::Shikimori("43".to_owned())),
+        ("mal:43", ExternalId::Shikimori("43".to_owned())),
+        (
+            "worldart:http://www.world-art.ru/animation/animation.php?id=1",
+            ExternalId::WorldArt("http://www.world-art.ru/animation/animation.php?id=1".to_owned()),
+        ),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(expected, input.parse().unwrap(), "{input}");
+    }
+
+    for input in [
+        "kp:abc",
+        "imdb:0111161",
+        "shiki:",
+        "worldart:https://example.com",
+        "https://shikimori.one/animes/43",
+        "43",
+    ] {
+        let err = input.parse::<ExternalId>().unwrap_err();
+        assert!(err.is_user_error(), "{input}");
+    }
+
+    assert_eq!(
+        "shiki:43",
+        "mal:43".parse::<ExternalId>().unwrap().to_string()
+    );
+}
+
+#[test]
+fn search_by_external_id() {
+    let id = ExternalId::Imdb("tt0111161".to_owned());
+    assert_eq!(
+        vec![("imdb_id", "tt0111161".to_owned())],
+        SearchRequest::external_id(&id).params()
+    );
+}
@@ -0,0 +1,7 @@
+#[cfg(feature = "catalog")]
+mod catalog;
+mod client;
+mod id;
+mod request;
+mod types;
+mod watch;
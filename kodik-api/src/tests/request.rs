@@ -0,0 +1,69 @@
+use crate::{
+    AggregateRequest, AggregateSort, Filters, GenresRequest, GenresType, ListRequest, ListSort,
+    MaterialType, Order, SearchRequest, TranslationType,
+};
+
+#[test]
+fn search_params() {
+    let request = SearchRequest {
+        with_episodes_data: true,
+        with_material_data: true,
+        prioritize_translations: vec![610, 609],
+        filters: Filters {
+            types: vec![MaterialType::AnimeSerial, MaterialType::Anime],
+            year: vec![2002],
+            translation_type: Some(TranslationType::Voice),
+            camrip: Some(false),
+            ..Filters::default()
+        },
+        ..SearchRequest::shikimori_id("20")
+    };
+
+    assert_eq!(
+        vec![
+            ("shikimori_id", "20".to_owned()),
+            ("prioritize_translations", "610,609".to_owned()),
+            ("with_episodes_data", "true".to_owned()),
+            ("with_material_data", "true".to_owned()),
+            ("types", "anime-serial,anime".to_owned()),
+            ("year", "2002".to_owned()),
+            ("camrip", "false".to_owned()),
+            ("translation_type", "voice".to_owned()),
+        ],
+        request.params()
+    );
+    assert!(SearchRequest::default().params().is_empty());
+}
+
+#[test]
+fn list_and_aggregate_params() {
+    let request = ListRequest {
+        limit: Some(100),
+        sort: Some(ListSort::ShikimoriRating),
+        order: Some(Order::Asc),
+        ..ListRequest::default()
+    };
+    assert_eq!(
+        vec![
+            ("limit", "100".to_owned()),
+            ("sort", "shikimori_rating".to_owned()),
+            ("order", "asc".to_owned()),
+        ],
+        request.params()
+    );
+
+    let request = GenresRequest {
+        genres_type: Some(GenresType::Shikimori),
+        aggregate: AggregateRequest {
+            sort: Some(AggregateSort::Count),
+            ..AggregateRequest::default()
+        },
+    };
+    assert_eq!(
+        vec![
+            ("sort", "count".to_owned()),
+            ("genres_type", "shikimori".to_owned()),
+        ],
+        request.params()
+    );
+}
@@ -0,0 +1,87 @@
+use crate::{Count, Episode, Material, MaterialType, Page, TranslationType, YearCount};
+
+#[test]
+fn parse_search_page() {
+    let json = r#"{
+        "time": "3 ms",
+        "total": 1,
+        "prev_page": null,
+        "next_page": null,
+        "results": [{
+            "id": "serial-45534",
+            "type": "anime-serial",
+            "link": "//kodik.info/serial/45534/76b3b8d7b0b0bf2b5a7c7fd2a2c4d2a3/720p",
+            "title": "Наруто",
+            "title_orig": "Naruto",
+            "translation": {"id": 610, "title": "AniLibria.TV", "type": "voice"},
+            "year": 2002,
+            "last_season": 1,
+            "last_episode": 220,
+            "episodes_count": 220,
+            "shikimori_id": "20",
+            "quality": "WEB-DLRip 720p",
+            "camrip": false,
+            "lgbt": false,
+            "blocked_countries": [],
+            "blocked_seasons": {},
+            "created_at": "2020-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "seasons": {
+                "1": {
+                    "link": "//kodik.info/season/1/abc/720p",
+                    "episodes": {
+                        "1": "//kodik.info/seria/1/abc/720p",
+                        "2": {"link": "//kodik.info/seria/2/abc/720p", "title": "Second", "screenshots": []}
+                    }
+                }
+            },
+            "screenshots": [],
+            "material_data": {
+                "anime_title": "Наруто",
+                "anime_kind": "tv",
+                "anime_status": "released",
+                "shikimori_rating": 8.0,
+                "episodes_total": 220,
+                "anime_licensors": ["Reanimedia"]
+            }
+        }]
+    }"#;
+
+    let page: Page<Material> = serde_json::from_str(json).unwrap();
+    assert_eq!(Some(1), page.total);
+    assert_eq!(None, page.next_page);
+
+    let material = &page.results[0];
+    assert_eq!(MaterialType::AnimeSerial, material.r#type);
+    assert!(material.r#type.is_serial());
+    assert_eq!(TranslationType::Voice, material.translation.r#type);
+
+    let episodes = &material.seasons.as_ref().unwrap()[&1].episodes;
+    assert!(matches!(episodes[&1], Episode::Link(_)));
+    assert_eq!("//kodik.info/seria/2/abc/720p", episodes[&2].link());
+
+    let data = material.material_data.as_ref().unwrap();
+    assert_eq!(Some(8.0), data.shikimori_rating);
+    assert!(data.other.contains_key("anime_licensors"));
+}
+
+#[test]
+fn parse_unknown_type_and_aggregations() {
+    let material: Material = serde_json::from_str(
+        r#"{"id": "movie-1", "type": "hologram", "link": "//kodik.info/video/1/a/720p",
+            "title": "X", "translation": {"id": 1, "title": "Y", "type": "subtitles"}}"#,
+    )
+    .unwrap();
+    assert_eq!(MaterialType::Other, material.r#type);
+    assert!(material.seasons.is_none());
+
+    let genres: Page<Count> = serde_json::from_str(
+        r#"{"time": "1 ms", "total": 1, "results": [{"title": "аниме", "count": 5}]}"#,
+    )
+    .unwrap();
+    assert_eq!(5, genres.results[0].count);
+
+    let years: Page<YearCount> =
+        serde_json::from_str(r#"{"results": [{"year": 2002, "count": 7}]}"#).unwrap();
+    assert_eq!(2002, years.results[0].year);
+}
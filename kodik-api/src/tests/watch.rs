@@ -0,0 +1,102 @@
+use kodik_utils::Error;
+use serde_json::json;
+
+use crate::{ExternalId, Material, Position, Track, WatchState};
+
+fn material(id: &str, translation_id: u32, episodes: &[u32]) -> Material {
+    let episodes = episodes
+        .iter()
+        .map(|ep| {
+            (
+                ep.to_string(),
+                json!(format!("//kodik.info/seria/{ep}/abc/720p")),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>();
+    serde_json::from_value(json!({
+        "id": id,
+        "type": "anime-serial",
+        "link": format!("//kodik.info/serial/{id}/abc/720p"),
+        "title": "Наруто",
+        "translation": {"id": translation_id, "title": "AniLibria.TV", "type": "voice"},
+        "shikimori_id": "20",
+        "last_season": 1,
+        "seasons": {"1": {"link": "//kodik.info/season/1/abc/720p", "episodes": episodes}}
+    }))
+    .unwrap()
+}
+
+#[test]
+fn track_test() {
+    let track: Track = "shiki:20@610".parse().unwrap();
+    assert_eq!(ExternalId::Shikimori("20".to_owned()), track.id);
+    assert_eq!(Some(610), track.translation_id);
+    assert_eq!("shiki:20@610", track.to_string());
+
+    let track: Track = "kp:326".parse().unwrap();
+    assert_eq!(None, track.translation_id);
+    assert_eq!("kp:326", track.to_string());
+
+    assert!(matches!(
+        "shiki:20@voice".parse::<Track>(),
+        Err(Error::InvalidArgument { .. })
+    ));
+    assert!("20@610".parse::<Track>().is_err());
+
+    let track: Track = "shiki:20@610".parse().unwrap();
+    assert!(track.matches(&material("serial-1", 610, &[1])));
+    assert!(!track.matches(&material("serial-1", 609, &[1])));
+}
+
+#[test]
+fn update_test() {
+    let tracks = ["shiki:20@610".parse::<Track>().unwrap()];
+    let mut state = WatchState::default();
+    state.remember(&tracks[0], &[material("serial-1", 610, &[1, 2])]);
+    assert_eq!(
+        Some(&Position {
+            season: 1,
+            episode: 2
+        }),
+        state.seen["shiki:20@610"].get("serial-1")
+    );
+
+    assert!(
+        state
+            .update(&tracks, &material("serial-1", 610, &[1, 2]))
+            .is_empty()
+    );
+
+    let new = state.update(&tracks, &material("serial-1", 610, &[1, 2, 3, 4]));
+    assert_eq!(
+        vec![3, 4],
+        new.iter().map(|new| new.episode).collect::<Vec<_>>()
+    );
+    assert_eq!("shiki:20@610", new[0].track);
+    assert_eq!(
+        Some("//kodik.info/seria/3/abc/720p"),
+        new[0].link.as_deref()
+    );
+    assert!(
+        state
+            .update(&tracks, &material("serial-1", 610, &[1, 2, 3, 4]))
+            .is_empty()
+    );
+
+    // Other translations are not tracked.
+    assert!(
+        state
+            .update(&tracks, &material("serial-2", 609, &[1, 2, 3, 4, 5]))
+            .is_empty()
+    );
+
+    // A new material of a track only reports its last episode.
+    let tracks = ["shiki:20".parse::<Track>().unwrap()];
+    let new = state.update(&tracks, &material("serial-2", 609, &[1, 2, 3, 4, 5]));
+    assert_eq!(
+        vec![("serial-2", 5)],
+        new.iter()
+            .map(|new| (new.material_id.as_str(), new.episode))
+            .collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,474 @@
+//! Responses of the Kodik API.
+//!
+//! Fields Kodik may leave out are `Option`s or default to empty, so a
+//! response only fails to parse if its shape changes.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// One page of results. `search` returns everything on one page, `list`
+/// links the following page in [`Page::next_page`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Page<T> {
+    /// Time Kodik took to answer, e.g. `3 ms`.
+    pub time: Option<String>,
+    /// Number of results across all pages.
+    pub total: Option<u64>,
+    /// URL of the previous page, if any.
+    pub prev_page: Option<String>,
+    /// URL of the next page, if any.
+    pub next_page: Option<String>,
+    /// Results on this page.
+    pub results: Vec<T>,
+}
+
+impl<T> Default for Page<T> {
+    fn default() -> Self {
+        Self {
+            time: None,
+            total: None,
+            prev_page: None,
+            next_page: None,
+            results: Vec::new(),
+        }
+    }
+}
+
+/// A movie or serial in one translation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Material {
+    /// Kodik id, e.g. `serial-45534`.
+    pub id: String,
+    /// Kind of material.
+    pub r#type: MaterialType,
+    /// Player link without a scheme, e.g. `//kodik.info/serial/45534/.../720p`.
+    pub link: String,
+    /// Russian title.
+    pub title: String,
+    /// Original title.
+    #[serde(default)]
+    pub title_orig: Option<String>,
+    /// Alternative titles separated by ` / `.
+    #[serde(default)]
+    pub other_title: Option<String>,
+    /// Translation of this material.
+    pub translation: Translation,
+    /// Release year.
+    #[serde(default)]
+    pub year: Option<u16>,
+    /// Number of the last season, for serials.
+    #[serde(default)]
+    pub last_season: Option<u32>,
+    /// Number of the last episode, for serials.
+    #[serde(default)]
+    pub last_episode: Option<u32>,
+    /// Number of episodes, for serials.
+    #[serde(default)]
+    pub episodes_count: Option<u32>,
+    /// Kinopoisk id.
+    #[serde(default)]
+    pub kinopoisk_id: Option<String>,
+    /// IMDb id, e.g. `tt0409591`.
+    #[serde(default)]
+    pub imdb_id: Option<String>,
+    /// MyDramaList id.
+    #[serde(default)]
+    pub mdl_id: Option<String>,
+    /// World Art page.
+    #[serde(default)]
+    pub worldart_link: Option<String>,
+    /// Shikimori id.
+    #[serde(default)]
+    pub shikimori_id: Option<String>,
+    /// Video quality, e.g. `WEB-DLRip 720p`.
+    #[serde(default)]
+    pub quality: Option<String>,
+    /// Whether the video is a camera recording.
+    #[serde(default)]
+    pub camrip: bool,
+    /// Whether the material has LGBT scenes.
+    #[serde(default)]
+    pub lgbt: bool,
+    /// Countries the material is blocked in.
+    #[serde(default)]
+    pub blocked_countries: Vec<String>,
+    /// Seasons blocked by country: `"all"` or a map of season numbers to
+    /// blocked episodes.
+    #[serde(default)]
+    pub blocked_seasons: Option<Value>,
+    /// Creation time, RFC 3339.
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// Time of the last update, RFC 3339.
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    /// Seasons by number, with `with_seasons` or `with_episodes`.
+    #[serde(default)]
+    pub seasons: Option<BTreeMap<u32, Season>>,
+    /// Screenshot URLs.
+    #[serde(default)]
+    pub screenshots: Vec<String>,
+    /// Data from Kinopoisk, Shikimori and others, with `with_material_data`.
+    #[serde(default)]
+    pub material_data: Option<MaterialData>,
+}
+
+/// A translation, also called a dub.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Translation {
+    /// Kodik id of the translation.
+    pub id: u32,
+    /// Name of the studio, e.g. `AniLibria.TV`.
+    pub title: String,
+    /// Voice-over or subtitles.
+    pub r#type: TranslationType,
+}
+
+/// A season of a serial.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Season {
+    /// Player link of the season.
+    #[serde(default)]
+    pub link: Option<String>,
+    /// Title of the season, if it has one.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Episodes by number, with `with_episodes` or `with_episodes_data`.
+    #[serde(default)]
+    pub episodes: BTreeMap<u32, Episode>,
+}
+
+/// An episode: a bare player link with `with_episodes`, or the link with
+/// its title and screenshots with `with_episodes_data`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Episode {
+    /// Player link of the episode.
+    Link(String),
+    /// Player link of the episode with its data.
+    Data {
+        /// Player link of the episode.
+        link: String,
+        /// Title of the episode, if it has one.
+        #[serde(default)]
+        title: Option<String>,
+        /// Screenshot URLs.
+        #[serde(default)]
+        screenshots: Vec<String>,
+    },
+}
+
+impl Episode {
+    /// Returns the player link of the episode.
+    #[must_use]
+    pub fn link(&self) -> &str {
+        match self {
+            Self::Link(link) | Self::Data { link, .. } => link,
+        }
+    }
+
+    /// Returns the player link of the episode, consuming it.
+    #[must_use]
+    pub fn into_link(self) -> String {
+        match self {
+            Self::Link(link) | Self::Data { link, .. } => link,
+        }
+    }
+}
+
+/// Data about a material gathered by Kodik from Kinopoisk, Shikimori,
+/// MyDramaList and others.
+///
+/// Only the common fields are typed, the rest are kept in
+/// [`MaterialData::other`].
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaterialData {
+    /// Russian title.
+    pub title: Option<String>,
+    /// Russian title on Shikimori.
+    pub anime_title: Option<String>,
+    /// English title.
+    pub title_en: Option<String>,
+    /// Alternative titles.
+    pub other_titles: Vec<String>,
+    /// Alternative English titles.
+    pub other_titles_en: Vec<String>,
+    /// Alternative Japanese titles.
+    pub other_titles_jp: Vec<String>,
+    /// Description from Kinopoisk.
+    pub description: Option<String>,
+    /// Description from Shikimori.
+    pub anime_description: Option<String>,
+    /// Poster URL from Kinopoisk.
+    pub poster_url: Option<String>,
+    /// Poster URL from Shikimori.
+    pub anime_poster_url: Option<String>,
+    /// Duration of a film or an episode, in minutes.
+    pub duration: Option<u32>,
+    /// Countries of production.
+    pub countries: Vec<String>,
+    /// Genres from every source.
+    pub all_genres: Vec<String>,
+    /// Genres from Kinopoisk.
+    pub genres: Vec<String>,
+    /// Genres from Shikimori.
+    pub anime_genres: Vec<String>,
+    /// Studios from Shikimori.
+    pub anime_studios: Vec<String>,
+    /// Anime kind from Shikimori.
+    pub anime_kind: Option<AnimeKind>,
+    /// Anime status from Shikimori.
+    pub anime_status: Option<AnimeStatus>,
+    /// Kinopoisk rating.
+    pub kinopoisk_rating: Option<f64>,
+    /// IMDb rating.
+    pub imdb_rating: Option<f64>,
+    /// Shikimori rating.
+    pub shikimori_rating: Option<f64>,
+    /// MyDramaList rating.
+    pub mydramalist_rating: Option<f64>,
+    /// World premiere date, `YYYY-MM-DD`.
+    pub premiere_world: Option<String>,
+    /// Date the first episode aired, `YYYY-MM-DD`.
+    pub aired_at: Option<String>,
+    /// Date the last episode aired, `YYYY-MM-DD`.
+    pub released_at: Option<String>,
+    /// Air time of the next episode, RFC 3339.
+    pub next_episode_at: Option<String>,
+    /// MPAA rating, e.g. `pg_13`.
+    pub rating_mpaa: Option<String>,
+    /// Minimal viewer age.
+    pub minimal_age: Option<u32>,
+    /// Planned number of episodes.
+    pub episodes_total: Option<u32>,
+    /// Number of episodes aired.
+    pub episodes_aired: Option<u32>,
+    /// Actors.
+    pub actors: Vec<String>,
+    /// Directors.
+    pub directors: Vec<String>,
+    /// Fields not covered above.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// Number of materials with a given title, as returned by `genres`,
+/// `countries` and `qualities`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Count {
+    /// Genre, country or quality.
+    pub title: String,
+    /// Number of materials.
+    pub count: u64,
+}
+
+/// Number of materials in a translation, as returned by `translations`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranslationCount {
+    /// Kodik id of the translation.
+    pub id: u32,
+    /// Name of the studio.
+    pub title: String,
+    /// Number of materials.
+    pub count: u64,
+}
+
+/// Number of materials released in a year, as returned by `years`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YearCount {
+    /// Release year.
+    pub year: u16,
+    /// Number of materials.
+    pub count: u64,
+}
+
+/// Kind of a material.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum MaterialType {
+    /// Foreign film.
+    ForeignMovie,
+    /// Soviet cartoon.
+    SovietCartoon,
+    /// Foreign cartoon.
+    ForeignCartoon,
+    /// Russian cartoon.
+    RussianCartoon,
+    /// Anime film.
+    Anime,
+    /// Russian film.
+    RussianMovie,
+    /// Animated series.
+    CartoonSerial,
+    /// Documentary series.
+    DocumentarySerial,
+    /// Russian series.
+    RussianSerial,
+    /// Foreign series.
+    ForeignSerial,
+    /// Anime series.
+    AnimeSerial,
+    /// Film in several parts.
+    MultiPartFilm,
+    /// A type unknown to this crate, only found in responses.
+    #[serde(other)]
+    Other,
+}
+
+impl MaterialType {
+    /// Every known type, in the order Kodik documents them.
+    pub const ALL: [Self; 12] = [
+        Self::ForeignMovie,
+        Self::SovietCartoon,
+        Self::ForeignCartoon,
+        Self::RussianCartoon,
+        Self::Anime,
+        Self::RussianMovie,
+        Self::CartoonSerial,
+        Self::DocumentarySerial,
+        Self::RussianSerial,
+        Self::ForeignSerial,
+        Self::AnimeSerial,
+        Self::MultiPartFilm,
+    ];
+
+    /// Returns the type Kodik calls `name`, e.g. `anime-serial`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == name)
+    }
+
+    /// Returns the name Kodik uses, e.g. `anime-serial`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ForeignMovie => "foreign-movie",
+            Self::SovietCartoon => "soviet-cartoon",
+            Self::ForeignCartoon => "foreign-cartoon",
+            Self::RussianCartoon => "russian-cartoon",
+            Self::Anime => "anime",
+            Self::RussianMovie => "russian-movie",
+            Self::CartoonSerial => "cartoon-serial",
+            Self::DocumentarySerial => "documentary-serial",
+            Self::RussianSerial => "russian-serial",
+            Self::ForeignSerial => "foreign-serial",
+            Self::AnimeSerial => "anime-serial",
+            Self::MultiPartFilm => "multi-part-film",
+            Self::Other => "other",
+        }
+    }
+
+    /// Returns `true` for series, which have seasons and episodes.
+    #[must_use]
+    pub const fn is_serial(self) -> bool {
+        matches!(
+            self,
+            Self::CartoonSerial
+                | Self::DocumentarySerial
+                | Self::RussianSerial
+                | Self::ForeignSerial
+                | Self::AnimeSerial
+        )
+    }
+}
+
+/// Kind of a translation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranslationType {
+    /// Voice-over.
+    Voice,
+    /// Subtitles.
+    Subtitles,
+}
+
+impl TranslationType {
+    /// Returns the name Kodik uses.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Voice => "voice",
+            Self::Subtitles => "subtitles",
+        }
+    }
+}
+
+/// Kind of an anime on Shikimori.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum AnimeKind {
+    /// TV series.
+    Tv,
+    /// TV series of up to 13 episodes.
+    Tv13,
+    /// TV series of up to 24 episodes.
+    Tv24,
+    /// TV series of more than 24 episodes.
+    Tv48,
+    /// Film.
+    Movie,
+    /// Original video animation.
+    Ova,
+    /// Original net animation.
+    Ona,
+    /// Special.
+    Special,
+    /// Music video.
+    Music,
+    /// A kind unknown to this crate, only found in responses.
+    #[serde(other)]
+    Other,
+}
+
+impl AnimeKind {
+    /// Returns the name Kodik uses.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Tv => "tv",
+            Self::Tv13 => "tv13",
+            Self::Tv24 => "tv24",
+            Self::Tv48 => "tv48",
+            Self::Movie => "movie",
+            Self::Ova => "ova",
+            Self::Ona => "ona",
+            Self::Special => "special",
+            Self::Music => "music",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Airing status of an anime on Shikimori.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum AnimeStatus {
+    /// Announced.
+    Anons,
+    /// Airing.
+    Ongoing,
+    /// Finished airing.
+    Released,
+    /// A status unknown to this crate, only found in responses.
+    #[serde(other)]
+    Other,
+}
+
+impl AnimeStatus {
+    /// Returns the name Kodik uses.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Anons => "anons",
+            Self::Ongoing => "ongoing",
+            Self::Released => "released",
+            Self::Other => "other",
+        }
+    }
+}
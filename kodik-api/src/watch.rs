@@ -0,0 +1,267 @@
+//! Following materials for new episodes.
+//!
+//! A [`WatchState`] remembers the last episode of every tracked material and
+//! the newest update seen on the `list` endpoint. [`WatchState::poll`] fetches
+//! the materials updated since, newest first, and reports the episodes added
+//! to tracked ones.
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use kodik_utils::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::KodikApi,
+    id::ExternalId,
+    request::{Filters, ListRequest, ListSort, Order},
+    types::{Material, Translation},
+};
+
+/// Materials requested per `list` page, the most Kodik allows.
+const PAGE_LIMIT: u32 = 100;
+
+/// A tracked material: an id on another site and, optionally, the Kodik id
+/// of the only translation to follow.
+///
+/// Parses from and displays as `prefix:id` or `prefix:id@translation`, e.g.
+/// `shiki:20@610`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Track {
+    /// Id of the material on another site.
+    pub id: ExternalId,
+    /// Kodik translation id, every translation if `None`.
+    pub translation_id: Option<u32>,
+}
+
+impl Track {
+    /// Returns `true` if `material` is tracked by `self`.
+    #[must_use]
+    pub fn matches(&self, material: &Material) -> bool {
+        self.id.matches(material)
+            && self
+                .translation_id
+                .is_none_or(|id| id == material.translation.id)
+    }
+}
+
+impl From<ExternalId> for Track {
+    fn from(id: ExternalId) -> Self {
+        Self {
+            id,
+            translation_id: None,
+        }
+    }
+}
+
+impl fmt::Display for Track {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.translation_id {
+            Some(translation_id) => write!(f, "{}@{translation_id}", self.id),
+            None => self.id.fmt(f),
+        }
+    }
+}
+
+impl FromStr for Track {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once('@') {
+            Some((id, translation_id)) => Ok(Self {
+                id: id.parse()?,
+                translation_id: Some(translation_id.parse().map_err(|_| {
+                    Error::invalid_argument(s, "expected a numeric translation id after '@'")
+                })?),
+            }),
+            None => s.parse::<ExternalId>().map(Self::from),
+        }
+    }
+}
+
+/// Season and episode number, ordered by season first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub struct Position {
+    /// Season number.
+    pub season: u32,
+    /// Episode number within the season.
+    pub episode: u32,
+}
+
+/// An episode added to a tracked material.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NewEpisode {
+    /// The [`Track`] that matched, as displayed.
+    pub track: String,
+    /// Kodik id of the material.
+    pub material_id: String,
+    /// Title of the material.
+    pub title: String,
+    /// Translation the episode was released in.
+    pub translation: Translation,
+    /// Season number.
+    pub season: u32,
+    /// Episode number within the season.
+    pub episode: u32,
+    /// Player link of the episode, if Kodik listed its episodes.
+    pub link: Option<String>,
+}
+
+/// What a watcher has seen so far, saved between polls.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct WatchState {
+    /// `updated_at` of the newest material seen on the `list` endpoint.
+    pub updated_at: Option<String>,
+    /// Last episode of each tracked material, by track and Kodik material id.
+    #[serde(default)]
+    pub seen: BTreeMap<String, BTreeMap<String, Position>>,
+}
+
+impl WatchState {
+    /// Returns the episodes added to the materials of `tracks` since the last
+    /// poll.
+    ///
+    /// Tracks polled for the first time are looked up and only remembered,
+    /// the episodes they already have are not reported. Tracks missing from
+    /// `tracks` are forgotten.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`KodikApi::lookup`] and [`KodikApi::list`]. The
+    /// state is left as it was before the `list` requests, so the next poll
+    /// reports the same episodes.
+    pub async fn poll(
+        &mut self,
+        api: &KodikApi,
+        tracks: &[Track],
+    ) -> Result<Vec<NewEpisode>, Error> {
+        let keys = tracks.iter().map(ToString::to_string).collect::<Vec<_>>();
+        self.seen.retain(|key, _| keys.contains(key));
+        for (track, key) in tracks.iter().zip(&keys) {
+            if !self.seen.contains_key(key) {
+                let materials = api.lookup(&track.id).await?.results;
+                self.remember(track, &materials);
+            }
+        }
+
+        let translation_id = tracks
+            .iter()
+            .map(|track| track.translation_id)
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_default();
+        let request = ListRequest {
+            limit: Some(PAGE_LIMIT),
+            sort: Some(ListSort::UpdatedAt),
+            order: Some(Order::Desc),
+            with_episodes: true,
+            filters: Filters {
+                translation_id,
+                ..Filters::default()
+            },
+            ..ListRequest::default()
+        };
+
+        // Collected first, so that a failed request leaves the state as it was.
+        let since = self.updated_at.as_deref();
+        let mut materials = Vec::new();
+        let mut page = api.list(&request).await?;
+        loop {
+            let len = page.results.len();
+            let updated = page
+                .results
+                .drain(..)
+                .take_while(|material| {
+                    since.is_none_or(|since| {
+                        material
+                            .updated_at
+                            .as_deref()
+                            .is_none_or(|updated_at| updated_at >= since)
+                    })
+                })
+                .collect::<Vec<_>>();
+            // The first poll only needs the first page to know where it is.
+            let done = since.is_none() || updated.len() < len;
+            materials.extend(updated);
+            if done {
+                break;
+            }
+            match api.next_page(&page).await? {
+                Some(next) => page = next,
+                None => break,
+            }
+        }
+
+        let mut episodes = Vec::new();
+        for material in &materials {
+            episodes.extend(self.update(tracks, material));
+        }
+        if let Some(updated_at) = materials.first().and_then(|m| m.updated_at.clone()) {
+            self.updated_at = Some(updated_at);
+        }
+        Ok(episodes)
+    }
+
+    /// Remembers the last episode of the `materials` of `track` without
+    /// reporting anything.
+    pub fn remember(&mut self, track: &Track, materials: &[Material]) {
+        let seen = self.seen.entry(track.to_string()).or_default();
+        for material in materials.iter().filter(|m| track.matches(m)) {
+            if let Some((position, _)) = episodes(material).pop() {
+                seen.insert(material.id.clone(), position);
+            }
+        }
+    }
+
+    /// Returns the episodes of `material` after the last one remembered for
+    /// each of `tracks` that matches it, and remembers its last episode.
+    ///
+    /// A material new to a track, such as a new translation, only reports
+    /// its last episode.
+    pub fn update(&mut self, tracks: &[Track], material: &Material) -> Vec<NewEpisode> {
+        let all = episodes(material);
+        let Some(&(last, _)) = all.last() else {
+            return Vec::new();
+        };
+
+        let mut new = Vec::new();
+        for track in tracks.iter().filter(|track| track.matches(material)) {
+            let key = track.to_string();
+            let seen = self.seen.entry(key.clone()).or_default();
+            let after = seen.insert(material.id.clone(), last);
+            let skip = after.map_or(all.len() - 1, |after| {
+                all.partition_point(|(position, _)| *position <= after)
+            });
+            new.extend(all.iter().skip(skip).map(|(position, link)| NewEpisode {
+                track: key.clone(),
+                material_id: material.id.clone(),
+                title: material.title.clone(),
+                translation: material.translation.clone(),
+                season: position.season,
+                episode: position.episode,
+                link: link.clone(),
+            }));
+        }
+        new
+    }
+}
+
+/// Returns the episodes of `material` in order, with their links if Kodik
+/// listed them, or only its last episode otherwise. Films have none.
+fn episodes(material: &Material) -> Vec<(Position, Option<String>)> {
+    match &material.seasons {
+        Some(seasons) if seasons.values().any(|s| !s.episodes.is_empty()) => seasons
+            .iter()
+            .flat_map(|(&season, s)| {
+                s.episodes.iter().map(move |(&episode, e)| {
+                    (Position { season, episode }, Some(e.link().to_owned()))
+                })
+            })
+            .collect(),
+        _ => material
+            .last_episode
+            .map(|episode| {
+                let season = material.last_season.unwrap_or(1);
+                (Position { season, episode }, None)
+            })
+            .into_iter()
+            .collect(),
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use kodik_utils::{Error, Stage, http::error_reason};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+
+const RATE_LIMIT_MARKERS: &[&str] = &["too many requests", "слишком много запросов"];
+
+const GEO_MARKERS: &[&str] = &[
+    "в вашей стране",
+    "в вашем регионе",
+    "in your country",
+    "in your region",
+];
+
+const COPYRIGHT_MARKERS: &[&str] = &["правообладател", "copyright holder", "dmca"];
+
+const REMOVED_MARKERS: &[&str] = &[
+    "видео не найдено",
+    "файл не найден",
+    "видео удалено",
+    "видео было удалено",
+    "video not found",
+    "file not found",
+    "video was removed",
+    "video has been removed",
+];
+
+/// Recognises pages and responses Kodik serves instead of a playable video.
+///
+/// Returns `None` if the body does not look like a known error page.
+pub fn detect(
+    stage: Stage,
+    url: &str,
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    body: &str,
+) -> Option<Error> {
+    let text = body.to_lowercase();
+    let has = |markers: &[&str]| markers.iter().any(|marker| text.contains(marker));
+    let url = url.to_owned();
+    let reason = error_reason(body);
+
+    if status == StatusCode::TOO_MANY_REQUESTS || has(RATE_LIMIT_MARKERS) {
+        return Some(Error::RateLimited {
+            stage,
+            url,
+            retry_after,
+            reason,
+        });
+    }
+
+    if has(GEO_MARKERS) {
+        Some(Error::GeoBlocked { stage, url, reason })
+    } else if has(COPYRIGHT_MARKERS) || status == StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS {
+        Some(Error::CopyrightBlocked { stage, url, reason })
+    } else if has(REMOVED_MARKERS) || (names_video(stage) && is_gone(status)) {
+        Some(Error::VideoRemoved { stage, url, reason })
+    } else {
+        None
+    }
+}
+
+/// Whether a request at `stage` is for the video itself, so that a missing
+/// page means the video is gone. A missing player script or endpoint does not.
+const fn names_video(stage: Stage) -> bool {
+    matches!(stage, Stage::PageFetch | Stage::Post)
+}
+
+fn is_gone(status: StatusCode) -> bool {
+    matches!(status, StatusCode::NOT_FOUND | StatusCode::GONE)
+}
+
+/// Replaces an extraction error with a more specific one if the page turns
+/// out to be an error page.
+pub fn or_unavailable(error: Error, stage: Stage, url: &str, html: &str) -> Error {
+    detect(stage, url, StatusCode::OK, None, html).unwrap_or(error)
+}
+
+/// Returns the body of a response, or the most specific error for a failed one.
+pub fn check_body(
+    stage: Stage,
+    url: &str,
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    failure: Option<reqwest::Error>,
+    body: String,
+) -> Result<String, Error> {
+    match failure {
+        None => Ok(body),
+        Some(failure) => Err(detect(stage, url, status, retry_after, &body)
+            .unwrap_or_else(|| Error::request(stage, url)(failure))),
+    }
+}
+
+/// Deserializes a JSON body, recognising error pages served in its place.
+pub fn parse_json<T: DeserializeOwned>(stage: Stage, url: &str, body: &str) -> Result<T, Error> {
+    serde_json::from_str(body).map_err(|e| {
+        detect(stage, url, StatusCode::OK, None, body)
+            .unwrap_or_else(|| Error::decode(stage, url)(e))
+    })
+}
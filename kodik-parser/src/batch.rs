@@ -0,0 +1,110 @@
+use std::{collections::HashMap, sync::Arc};
+
+use futures_util::{Stream, StreamExt as _, stream};
+use kodik_utils::Error;
+use reqwest::Client;
+
+use crate::{Response, parse, parser::VideoInfo};
+
+/// Result of a single URL in a [`parse_many`] batch.
+///
+/// The error is shared because duplicate URLs in a batch are resolved once.
+pub type BatchResult = Result<Response, Arc<Error>>;
+
+/// Parses many Kodik player pages concurrently.
+///
+/// Yields one result per input URL, in input order, as soon as that URL and
+/// every URL before it are resolved. At most `limit` pages are parsed at once.
+///
+/// URLs referring to the same video are parsed only once and share the result.
+/// Endpoint discovery is shared across the whole batch through
+/// [`crate::KODIK_STATE`], so a cold cache costs a single lookup.
+///
+/// # Example
+/// ```no_run
+/// use kodik_parser::reqwest::Client;
+/// use futures_util::StreamExt;
+///
+/// # async fn run() {
+/// let client = Client::new();
+/// let urls = [
+///     "https://kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p",
+///     "https://kodikplayer.com/video/115369/2eb2c698195c8a5020284d37dbc981a3/720p",
+/// ];
+///
+/// let mut results = std::pin::pin!(kodik_parser::parse_many(&client, urls, 4));
+/// while let Some(result) = results.next().await {
+///     println!("{result:#?}");
+/// }
+/// # }
+/// ```
+pub fn parse_many<I>(client: &Client, urls: I, limit: usize) -> impl Stream<Item = BatchResult> + '_
+where
+    I: IntoIterator,
+    I::Item: Into<String>,
+{
+    let (jobs, order) = dedup(urls.into_iter().map(Into::into));
+
+    let mut remaining = vec![0_usize; jobs.len()];
+    for &job in &order {
+        if let Some(count) = remaining.get_mut(job) {
+            *count += 1;
+        }
+    }
+
+    log::debug!(
+        "Parsing {} urls ({} unique) with limit {limit}...",
+        order.len(),
+        jobs.len()
+    );
+
+    let results = stream::iter(jobs)
+        .map(move |url| async move { parse(client, &url).await.map_err(Arc::new) })
+        .buffered(limit.max(1));
+
+    let done: Vec<Option<BatchResult>> = Vec::new();
+
+    stream::unfold(
+        (Box::pin(results), order.into_iter(), done, remaining),
+        |(mut results, mut order, mut done, mut remaining)| async move {
+            let job = order.next()?;
+
+            while done.len() <= job {
+                done.push(Some(results.next().await?));
+            }
+
+            let count = remaining.get_mut(job)?;
+            *count -= 1;
+            let slot = done.get_mut(job)?;
+            let item = if *count == 0 {
+                slot.take()?
+            } else {
+                slot.clone()?
+            };
+
+            Some((item, (results, order, done, remaining)))
+        },
+    )
+}
+
+/// Splits URLs into unique jobs, in order of first appearance, and the job
+/// index of every input URL.
+pub fn dedup(urls: impl Iterator<Item = String>) -> (Vec<String>, Vec<usize>) {
+    let mut jobs = Vec::new();
+    let mut seen = HashMap::new();
+
+    let order = urls
+        .map(|url| {
+            let key =
+                VideoInfo::from_url(&url).map_or_else(|_| url.trim().to_owned(), |info| info.key());
+            let next = jobs.len();
+            let job = *seen.entry(key).or_insert(next);
+            if job == next {
+                jobs.push(url);
+            }
+            job
+        })
+        .collect();
+
+    (jobs, order)
+}
@@ -0,0 +1,98 @@
+//! Synchronous counterpart of [`crate::parse`].
+//!
+//! Enabled by the `blocking` feature. Runs the same pipeline as the async API
+//! with a blocking transport, and shares the endpoint cache and shift with it
+//! through [`crate::KODIK_STATE`].
+
+use std::{
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+use crate::{
+    Response, availability, parser,
+    scraper::{Request, Transport, retry_scheduled},
+    trace,
+};
+use kodik_utils::{Error, RATE_LIMITER};
+use reqwest::blocking::Client;
+
+/// Parses a Kodik player page and returns structured video stream information.
+///
+/// Blocking version of [`crate::parse`], performing the same sequence of
+/// operations with a [`reqwest::blocking::Client`].
+///
+/// # Errors
+/// Returns an error if:
+/// - The domain cannot be extracted from the URL.
+/// - Network requests fail.
+/// - HTML parsing fails due to unexpected format changes.
+/// - The API endpoint cannot be found.
+/// - Link decoding fails.
+///
+/// # Example
+/// ```no_run
+/// use kodik_parser::reqwest::blocking::Client;
+///
+/// let client = Client::new();
+/// let url = "https://kodikplayer.com/some-type/some-id/some-hash/some-quality";
+/// let kodik_response = kodik_parser::blocking::parse(&client, url).unwrap();
+///
+/// let link_720 = &kodik_response.links.quality_720.first().unwrap().src;
+/// println!("Link with 720p quality is: {link_720}");
+/// ```
+pub fn parse(client: &Client, url: &str) -> Result<Response, Error> {
+    block_on(parser::run(client, url))
+}
+
+impl Transport for Client {
+    async fn send(&self, request: &Request<'_>) -> Result<String, Error> {
+        let Request {
+            stage,
+            url,
+            ref headers,
+            form,
+        } = *request;
+        let build = || {
+            form.map_or_else(|| self.get(url), |form| self.post(url).form(form))
+                .headers(headers.clone())
+        };
+        let response =
+            RATE_LIMITER.send_blocking(stage, url, build, retry_scheduled(stage, url))?;
+
+        let status = response.status();
+        trace::record("status", &status.as_u16());
+        let retry_after = kodik_utils::http::retry_after(response.headers());
+        let failure = response.error_for_status_ref().err();
+        let body = response.text().map_err(Error::request(stage, url))?;
+
+        availability::check_body(stage, url, status, retry_after, failure, body)
+    }
+}
+
+/// Wakes the thread blocked in [`block_on`].
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Runs `future` to completion on the current thread.
+///
+/// Requests of the blocking transport complete before they return, so the
+/// thread only parks while another parse rediscovers the endpoint.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
@@ -1,6 +1,6 @@
-use crate::{KODIK_STATE, scraper::Response};
+use crate::{KODIK_STATE, scraper::Response, trace};
 use base64::{Engine as _, engine::general_purpose};
-use kodik_utils::Error;
+use kodik_utils::{BoxError, Error, redact::Redacted};
 
 const MIN_SHIFT: u8 = 0;
 const MAX_SHIFT: u8 = 26;
@@ -9,8 +9,19 @@ const MAX_SHIFT: u8 = 26;
 ///
 /// # Errors
 ///
-/// Returns a `KodikError` if decoding fails for any of the links.
+/// Returns `Error::LinkCannotBeDecoded` if decoding fails for any of the links.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "decode",
+        level = "debug",
+        skip_all,
+        err,
+        fields(shift = tracing::field::Empty, elapsed_ms = tracing::field::Empty),
+    )
+)]
 pub fn decode_links(kodik_response: &mut Response) -> Result<(), Error> {
+    let _timer = trace::Timer::start();
     log::debug!("Decoding links...");
 
     for link in &mut kodik_response.links.quality_360 {
@@ -33,7 +44,11 @@ pub fn decode_links(kodik_response: &mut Response) -> Result<(), Error> {
         };
     }
 
-    log::trace!("Decoded links: {:#?}", kodik_response.links);
+    trace::record("shift", &KODIK_STATE.shift());
+    log::trace!(
+        "Decoded links: {}",
+        Redacted(format_args!("{:#?}", kodik_response.links))
+    );
     Ok(())
 }
 
@@ -54,7 +69,7 @@ pub fn decode_link(src: &str) -> Result<String, Error> {
     Err(Error::LinkCannotBeDecoded(src.to_owned()))
 }
 
-pub fn try_decode(encoded: &str, shift: u8) -> Result<String, Error> {
+pub fn try_decode(encoded: &str, shift: u8) -> Result<String, BoxError> {
     let mut decoded_caesar = caesar_cipher(encoded, shift);
 
     while !decoded_caesar.len().is_multiple_of(4) {
@@ -92,8 +107,8 @@ pub fn caesar_cipher(text: &str, shift: u8) -> String {
 ///
 /// # Errors
 ///
-/// Returns a `KodikError` if decoding fails due to invalid base64 input or invalid UTF-8.
-pub fn decode_base64(input: &str) -> Result<String, Error> {
+/// Returns an error if decoding fails due to invalid base64 input or invalid UTF-8.
+pub fn decode_base64(input: &str) -> Result<String, BoxError> {
     let decoded_input = general_purpose::STANDARD.decode(input)?;
     Ok(String::from_utf8(decoded_input)?)
 }
@@ -1,17 +1,35 @@
 //! # Kodik Parser library.
 //! `kodik-parser` for getting direct links to files from Kodik.
+//!
+//! The parser does not depend on a particular async runtime. Note that
+//! [`reqwest::Client`] itself needs a tokio reactor, so under smol or
+//! async-std wrap the calls with a compatibility layer such as `async-compat`.
 
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used, clippy::indexing_slicing)]
 mod tests;
 
+pub(crate) mod availability;
+pub(crate) mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub(crate) mod decoder;
+pub(crate) mod link;
+pub(crate) mod observer;
 pub(crate) mod parser;
+#[cfg(feature = "serde")]
+pub mod schema;
 pub(crate) mod scraper;
+pub(crate) mod snapshot;
 pub(crate) mod state;
+pub(crate) mod trace;
 
+pub use batch::{BatchResult, parse_many};
+pub use link::{LinkParams, StreamKind};
+pub use observer::{Event, Observer, ObserverId};
 pub use parser::parse;
 pub use scraper::{Link, Links, Response};
+pub use snapshot::{DomainState, SNAPSHOT_VERSION, StateSnapshot};
 pub use state::KODIK_STATE;
 
 pub extern crate reqwest;
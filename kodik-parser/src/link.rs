@@ -0,0 +1,168 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::Link;
+use kodik_utils::{Error, Stage, redact::Redacted};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Offset of Moscow time from UTC, in seconds.
+const MSK_OFFSET: u64 = 3 * 60 * 60;
+
+/// Delivery format of a video stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[non_exhaustive]
+pub enum StreamKind {
+    /// HTTP Live Streaming playlist.
+    Hls,
+    /// Progressive MP4 file.
+    Mp4,
+    /// Format not recognised.
+    Unknown,
+}
+
+/// Signing components of a decoded Kodik CDN link.
+///
+/// A decoded link looks like
+/// `https://p56.kodik.info/s/m/<file-id>/<signature>:<YYYYMMDDHH>/720.mp4:hls:manifest.m3u8`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkParams {
+    /// CDN node serving the file, e.g. `p56.kodik.info`.
+    pub node: String,
+    /// Encoded storage path of the file.
+    pub file_id: String,
+    /// Hex signature of the link.
+    pub signature: String,
+    /// Raw expiry stamp in `YYYYMMDDHH` form.
+    pub expires: u64,
+    /// Vertical resolution of the stream.
+    pub quality: u16,
+}
+
+impl LinkParams {
+    /// Parses the signing components out of a decoded CDN URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnexpectedResponse` if the URL is not a signed Kodik CDN link
+    /// or its expiry stamp is not a valid date.
+    pub fn parse(src: &str) -> Result<Self, Error> {
+        let link_re = lazy_regex::regex!(
+            r"^https://(?P<node>[^/]+)/s/m/(?P<file>[^/]+)/(?P<sig>[0-9a-f]+):(?P<exp>\d{10})/(?P<quality>\d+)\.mp4"
+        );
+
+        log::trace!("Parsing link params from {}...", Redacted(src));
+
+        let caps = link_re
+            .captures(src)
+            .ok_or_else(|| Error::unexpected(Stage::Decode, src, "not a signed link"))?;
+
+        let expires = caps["exp"]
+            .parse()
+            .map_err(|_| Error::unexpected(Stage::Decode, src, "invalid expiry"))?;
+        let quality = caps["quality"]
+            .parse()
+            .map_err(|_| Error::unexpected(Stage::Decode, src, "invalid quality"))?;
+
+        let params = Self {
+            node: caps["node"].to_owned(),
+            file_id: caps["file"].to_owned(),
+            signature: caps["sig"].to_owned(),
+            expires,
+            quality,
+        };
+
+        if params.expires_at().is_none() {
+            return Err(Error::unexpected(Stage::Decode, src, "invalid expiry"));
+        }
+
+        Ok(params)
+    }
+
+    /// Returns the moment the link stops being served.
+    ///
+    /// The stamp is interpreted as Moscow time (UTC+3), which errs on the side
+    /// of treating a link as expired early should Kodik stamp it in UTC.
+    #[must_use]
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        let hour = self.expires % 100;
+        let day = self.expires / 100 % 100;
+        let month = self.expires / 10_000 % 100;
+        let year = self.expires / 1_000_000;
+
+        if hour > 23
+            || !(1..=12).contains(&month)
+            || !(1..=days_in_month(year, month)).contains(&day)
+            || year < 1970
+        {
+            return None;
+        }
+
+        let secs = days_from_civil(year, month, day) * 86_400 + hour * 3_600;
+        Some(UNIX_EPOCH + Duration::from_secs(secs.checked_sub(MSK_OFFSET)?))
+    }
+
+    /// Returns `true` if the link has already expired.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at()
+            .is_none_or(|expires_at| expires_at <= SystemTime::now())
+    }
+}
+
+impl Link {
+    /// Returns the delivery format of the stream, judged by its MIME type or,
+    /// failing that, by the link itself.
+    #[must_use]
+    pub fn kind(&self) -> StreamKind {
+        let mime = self.r#type.to_ascii_lowercase();
+
+        if mime.contains("mpegurl") || self.src.contains(":hls:") {
+            StreamKind::Hls
+        } else if mime == "video/mp4" {
+            StreamKind::Mp4
+        } else {
+            StreamKind::Unknown
+        }
+    }
+
+    /// Parses the signing components of a decoded link.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UnexpectedResponse` if the link is not decoded yet or is not a
+    /// signed Kodik CDN link.
+    pub fn params(&self) -> Result<LinkParams, Error> {
+        LinkParams::parse(&self.src)
+    }
+
+    /// Returns `true` if the link has expired or its expiry cannot be determined.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.params().ok().is_none_or(|params| params.is_expired())
+    }
+}
+
+/// Number of days in `month` of `year`, 1-based.
+const fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of days since the Unix epoch for a proleptic Gregorian date.
+const fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
@@ -0,0 +1,111 @@
+use std::{sync::Arc, time::Duration};
+
+use kodik_utils::{Error, Stage};
+
+/// Progress of [`crate::parse`], reported to every registered [`Observer`].
+///
+/// Events borrow from the parser, clone what you need to keep.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum Event<'a> {
+    /// A page was downloaded: the player page or the player script.
+    PageFetched {
+        /// What the page was fetched for.
+        stage: Stage,
+        /// URL of the page.
+        url: &'a str,
+    },
+    /// Video type, id and hash were found, in the URL or on the player page.
+    VideoInfoExtracted {
+        /// Player page URL.
+        url: &'a str,
+        /// Kodik video id.
+        video_id: &'a str,
+    },
+    /// A cached API endpoint is about to be used.
+    EndpointCacheHit {
+        /// The cached endpoint.
+        endpoint: &'a str,
+    },
+    /// No endpoint is cached, it has to be discovered.
+    EndpointCacheMiss,
+    /// A new API endpoint was discovered and cached.
+    EndpointRediscovered {
+        /// The new endpoint.
+        endpoint: &'a str,
+    },
+    /// A player page links to a different player build than the one the
+    /// cached endpoint was found in. The endpoint is dropped and rediscovered
+    /// on the next request.
+    PlayerBuildChanged {
+        /// Domain serving the new build.
+        domain: &'a str,
+        /// Build the cached endpoint was found in.
+        old: &'a str,
+        /// Newly seen build.
+        new: &'a str,
+    },
+    /// Links turned out to be encoded with a different shift, which is now cached.
+    ShiftChanged {
+        /// Previous shift.
+        old: u8,
+        /// New shift.
+        new: u8,
+    },
+    /// Links of a video were decoded.
+    LinksDecoded {
+        /// Player page URL.
+        url: &'a str,
+        /// Number of decoded links across all qualities.
+        links: usize,
+    },
+    /// A request will be sent again: the server answered `429 Too Many
+    /// Requests`, or the cached endpoint was rejected and is rediscovered
+    /// first.
+    RetryScheduled {
+        /// URL of the rate-limited request, or the player page URL if the
+        /// endpoint was rejected.
+        url: &'a str,
+        /// Number of the upcoming attempt, starting at 2.
+        attempt: u32,
+        /// How long the request waits before the attempt.
+        pause: Duration,
+        /// Why the previous attempt failed.
+        error: &'a Error,
+    },
+}
+
+/// Receives [`Event`]s, registered with
+/// [`KODIK_STATE.subscribe`](crate::state::KodikState::subscribe).
+///
+/// Implemented for every `Fn(&Event)` closure. Observers are called
+/// synchronously on the parsing task, so they should return quickly.
+pub trait Observer: Send + Sync {
+    /// Handles a single event.
+    fn on_event(&self, event: &Event<'_>);
+}
+
+impl<F: Fn(&Event<'_>) + Send + Sync> Observer for F {
+    fn on_event(&self, event: &Event<'_>) {
+        self(event);
+    }
+}
+
+/// Handle of a registered [`Observer`], used to unsubscribe it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(pub(crate) u64);
+
+/// Registered observer together with its handle.
+#[derive(Clone)]
+pub struct Registration {
+    pub id: ObserverId,
+    pub observer: Arc<dyn Observer>,
+}
+
+impl std::fmt::Debug for Registration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Registration")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,7 +1,11 @@
+use std::time::Duration;
+
+use crate::availability;
 use crate::decoder;
-use crate::scraper;
-use crate::{KODIK_STATE, Response};
-use kodik_utils::Error;
+use crate::scraper::{self, Transport};
+use crate::trace;
+use crate::{Event, KODIK_STATE, Response};
+use kodik_utils::{Error, Stage};
 use reqwest::Client;
 use serde::Serialize;
 
@@ -9,7 +13,7 @@ use serde::Serialize;
 pub struct VideoInfo<'a> {
     r#type: &'a str,
     hash: &'a str,
-    id: &'a str,
+    pub(crate) id: &'a str,
     bad_user: &'static str,
     info: &'static str,
     cdn_is_working: &'static str,
@@ -28,12 +32,17 @@ impl<'a> VideoInfo<'a> {
         }
     }
 
-    /// Extracts video information from response text.
+    /// Returns a key identifying the video regardless of the URL it came from.
+    pub(crate) fn key(&self) -> String {
+        format!("{}/{}/{}", self.r#type, self.id, self.hash)
+    }
+
+    /// Extracts video information from the player page at `url`.
     ///
     /// # Errors
     ///
-    /// Returns `KodikError::Regex` if any of the required video fields (type, hash, id) are not found in the response text.
-    pub(crate) fn from_response(html: &'_ str) -> Result<VideoInfo<'_>, Error> {
+    /// Returns `Error::UnexpectedResponse` if any of the required video fields (type, hash, id) are not found in the response text.
+    pub(crate) fn from_response<'h>(url: &str, html: &'h str) -> Result<VideoInfo<'h>, Error> {
         let from_response_re = lazy_regex::regex!(r"\.(?P<field>type|hash|id) = '(?P<value>.*?)';");
 
         log::debug!("Extracting video info from response...");
@@ -43,40 +52,26 @@ impl<'a> VideoInfo<'a> {
         let mut id = None;
 
         for caps in from_response_re.captures_iter(html) {
+            let value = caps.name("value").map(|m| m.as_str());
             match &caps["field"] {
-                "type" => {
-                    r#type = Some(
-                        caps.name("value")
-                            .ok_or(Error::RegexMatch(
-                                "videoInfo.type value not found".to_owned(),
-                            ))?
-                            .as_str(),
-                    );
-                }
-                "hash" => {
-                    hash = Some(
-                        caps.name("value")
-                            .ok_or(Error::RegexMatch(
-                                "videoInfo.hash value not found".to_owned(),
-                            ))?
-                            .as_str(),
-                    );
-                }
-                "id" => {
-                    id = Some(
-                        caps.name("value")
-                            .ok_or(Error::RegexMatch("videoInfo.id value not found".to_owned()))?
-                            .as_str(),
-                    );
-                }
+                "type" => r#type = value,
+                "hash" => hash = value,
+                "id" => id = value,
                 _ => {}
             }
         }
 
+        let missing = |field| {
+            Error::unexpected(
+                Stage::VideoInfo,
+                url,
+                format!("videoInfo.{field} not found"),
+            )
+        };
         let video_info = VideoInfo::new(
-            r#type.ok_or(Error::RegexMatch("videoInfo.type not found".to_owned()))?,
-            hash.ok_or(Error::RegexMatch("videoInfo.hash not found".to_owned()))?,
-            id.ok_or(Error::RegexMatch("videoInfo.id not found".to_owned()))?,
+            r#type.ok_or_else(|| missing("type"))?,
+            hash.ok_or_else(|| missing("hash"))?,
+            id.ok_or_else(|| missing("id"))?,
         );
         log::trace!("Extracted video info: {video_info:#?}");
 
@@ -87,94 +82,98 @@ impl<'a> VideoInfo<'a> {
     ///
     /// # Errors
     ///
-    /// Returns `KodikError::Regex` if the video information (type, hash, id) is not found in the URL.
+    /// Returns `Error::InvalidUrl` if the video information (type, hash, id) is not found in the URL.
     pub(crate) fn from_url(url: &'_ str) -> Result<VideoInfo<'_>, Error> {
         let from_url_re = lazy_regex::regex!(r"/([^/]+)/(\d+)/([a-z0-9]+)");
 
         log::debug!("Extracting video info from url...");
 
-        let caps = from_url_re
-            .captures(url)
-            .ok_or(Error::RegexMatch(format!("videoInfo not found in '{url}'")))?;
-
-        let r#type = caps
-            .get(1)
-            .ok_or(Error::RegexMatch(format!(
-                "videoInfo.type not found in '{url}'"
-            )))?
-            .as_str();
-        let id = caps
-            .get(2)
-            .ok_or(Error::RegexMatch(format!(
-                "videoInfo.id not found in '{url}'"
-            )))?
-            .as_str();
-        let hash = caps
-            .get(3)
-            .ok_or(Error::RegexMatch(format!(
-                "videoInfo.hash not found in '{url}'"
-            )))?
-            .as_str();
+        let invalid = || Error::InvalidUrl {
+            url: url.to_owned(),
+            reason: "no video type, id and hash in path".to_owned(),
+        };
+
+        let caps = from_url_re.captures(url).ok_or_else(invalid)?;
+        let r#type = caps.get(1).ok_or_else(invalid)?.as_str();
+        let id = caps.get(2).ok_or_else(invalid)?.as_str();
+        let hash = caps.get(3).ok_or_else(invalid)?.as_str();
 
         Ok(VideoInfo::new(r#type, hash, id))
     }
 }
 
-/// Extracts the player URL from response text.
+/// Extracts the player script URL from the player page at `url`.
 ///
 /// # Errors
 ///
-/// Returns `KodikError::Regex` if the player path is not found in the response text.
-///
-/// # Panics
-///
-/// Panics if the regex capture group is not found, which should not happen if the regex is correct.
-pub fn extract_player_url(domain: &str, html: &str) -> Result<String, Error> {
+/// Returns `Error::UnexpectedResponse` if the player path is not found in the response text.
+pub fn extract_player_url(url: &str, html: &str) -> Result<String, Error> {
     let player_path_re = lazy_regex::regex!(
         r#"<script\s*type="text/javascript"\s*src="/(assets/js/app\.player_single[^"]*)""#
     );
 
     log::debug!("Extracting player url...");
+    let domain = kodik_utils::extract_domain(url)?;
     let player_path = player_path_re
         .captures(html)
-        .ok_or(Error::RegexMatch(
-            "there is no player path in response".to_owned(),
-        ))?
-        .get(1)
-        .ok_or(Error::RegexMatch(
-            "player path capture group not found".to_owned(),
-        ))?
+        .and_then(|caps| caps.get(1))
+        .ok_or_else(|| {
+            Error::unexpected(
+                Stage::PlayerScript,
+                url,
+                "there is no player path in response",
+            )
+        })?
         .as_str();
     log::trace!("Extracted player url: {player_path}");
 
     Ok(format!("https://{domain}/{player_path}"))
 }
 
-/// Extracts the API endpoint from player response text.
+/// Returns the build hash encoded in the player script URL, if any.
+pub fn player_fingerprint(player_url: &str) -> Option<&str> {
+    let fingerprint_re = lazy_regex::regex!(r"app\.player_single\.([0-9a-f]+)\.js");
+
+    fingerprint_re
+        .captures(player_url)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str())
+}
+
+/// Checks the player build linked from a freshly fetched player page against
+/// the one the cached endpoint was found in.
+pub fn check_player_build(url: &str, domain: &str, html: &str) {
+    if let Ok(player_url) = extract_player_url(url, html)
+        && let Some(fingerprint) = player_fingerprint(&player_url)
+    {
+        KODIK_STATE.observe_player_build(domain, fingerprint);
+    }
+}
+
+/// Extracts the API endpoint from the player script at `url`.
 ///
 /// # Errors
 ///
-/// Returns `KodikError::Regex` if the API endpoint is not found in the player response text.
-///
-/// # Panics
-///
-/// Panics if the regex capture group is not found, which should not happen if the regex is correct.
-pub fn extract_endpoint(html: &str) -> Result<String, Error> {
+/// Returns `Error::UnexpectedResponse` if the API endpoint is not found in the player response text,
+/// or `Error::Decode` if it cannot be decoded.
+pub fn extract_endpoint(url: &str, html: &str) -> Result<String, Error> {
     let endpoint_re = lazy_regex::regex!(r#"\$\.ajax\([^>]+,url:\s*atob\(["\']([\w=]+)["\']\)"#);
 
     log::debug!("Extracting endpoint...");
     let encoded_endpoint = endpoint_re
         .captures(html)
-        .ok_or(Error::RegexMatch(
-            "there is no api endpoint in player response".to_owned(),
-        ))?
-        .get(1)
-        .ok_or(Error::RegexMatch(
-            "api endpoint capture group not found".to_owned(),
-        ))?
+        .and_then(|caps| caps.get(1))
+        .ok_or_else(|| {
+            Error::unexpected(
+                Stage::Endpoint,
+                url,
+                "there is no api endpoint in player response",
+            )
+        })?
         .as_str();
 
-    let endpoint = decoder::decode_base64(encoded_endpoint)?;
+    let endpoint =
+        decoder::decode_base64(encoded_endpoint).map_err(Error::decode(Stage::Endpoint, url))?;
     log::trace!("Extracted endpoint: {endpoint}");
 
     Ok(endpoint)
@@ -193,13 +192,17 @@ pub fn extract_endpoint(html: &str) -> Result<String, Error> {
 /// 6. **Link decoding** – Decrypts and normalizes streaming URLs.
 ///
 /// The function uses a cached `VIDEO_INFO_ENDPOINT` to avoid repeated endpoint lookups.
+/// If the cached endpoint is rejected, it is rediscovered once before giving up.
+/// When the player page has to be fetched, the player build it links to is
+/// compared with the one the cached endpoint was found in, and a changed build
+/// triggers rediscovery up front.
 ///
 /// # Arguments
 /// * `client` – An [`reqwest::Client`] used for making HTTP requests.
 /// * `url` – A full Kodik player page URL.
 ///
 /// # Returns
-/// A [`KodikResponse`] containing structured player metadata and stream URLs.
+/// A [`Response`] containing structured player metadata and stream URLs.
 ///
 /// # Errors
 /// Returns an error if:
@@ -209,6 +212,8 @@ pub fn extract_endpoint(html: &str) -> Result<String, Error> {
 /// - The API endpoint cannot be found.
 /// - Link decoding fails.
 ///
+/// Every error carries the [`Stage`] it occurred in, see [`Error::stage`].
+///
 /// # Example
 /// ```no_run
 /// use kodik_parser::reqwest::Client;
@@ -223,46 +228,131 @@ pub fn extract_endpoint(html: &str) -> Result<String, Error> {
 /// # }
 /// ```
 pub async fn parse(client: &Client, url: &str) -> Result<Response, Error> {
+    run(client, url).await
+}
+
+/// The parse pipeline behind [`parse`] and `blocking::parse`.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "parse",
+        skip_all,
+        err,
+        fields(
+            url = %kodik_utils::redact::Redacted(url),
+            domain = tracing::field::Empty,
+            video_id = tracing::field::Empty,
+            retries = 0,
+            elapsed_ms = tracing::field::Empty,
+        ),
+    )
+)]
+pub async fn run(client: &impl Transport, url: &str) -> Result<Response, Error> {
+    let _timer = trace::Timer::start();
     let domain = kodik_utils::extract_domain(url)?;
+    trace::record("domain", domain);
     let mut html = String::new();
 
     let video_info = if let Ok(video_info) = VideoInfo::from_url(url) {
         video_info
     } else {
-        html = scraper::get(client, url).await?;
-        VideoInfo::from_response(&html)?
+        html = scraper::get(client, url, Stage::PageFetch).await?;
+        check_player_build(url, domain, &html);
+        VideoInfo::from_response(url, &html)
+            .map_err(|e| availability::or_unavailable(e, Stage::VideoInfo, url, &html))?
     };
+    trace::record("video_id", video_info.id);
+    KODIK_STATE.emit(&Event::VideoInfoExtracted {
+        url,
+        video_id: video_info.id,
+    });
+
+    let mut rediscovered = false;
+    let mut retries = 0_u32;
 
     loop {
         let endpoint = KODIK_STATE.endpoint();
 
-        if !endpoint.is_empty() {
-            if let Ok(mut kodik_response) =
-                scraper::post(client, domain, &endpoint, &video_info).await
-            {
-                decoder::decode_links(&mut kodik_response)?;
-                return Ok(kodik_response);
+        if endpoint.is_empty() {
+            KODIK_STATE.emit(&Event::EndpointCacheMiss);
+        } else {
+            KODIK_STATE.emit(&Event::EndpointCacheHit {
+                endpoint: &endpoint,
+            });
+            match scraper::post(client, domain, &endpoint, &video_info).await {
+                Ok(mut kodik_response) => {
+                    decoder::decode_links(&mut kodik_response)?;
+                    KODIK_STATE.touch_domain(domain);
+                    KODIK_STATE.emit(&Event::LinksDecoded {
+                        url,
+                        links: kodik_response.links.len(),
+                    });
+                    return Ok(kodik_response);
+                }
+                Err(e) if rediscovered || e.is_retryable() => return Err(e),
+                Err(e) => {
+                    log::warn!("Cached endpoint rejected: {e}");
+                    KODIK_STATE.clear_endpoint();
+                    retries += 1;
+                    trace::record("retries", &retries);
+                    KODIK_STATE.emit(&Event::RetryScheduled {
+                        url,
+                        attempt: retries + 1,
+                        pause: Duration::ZERO,
+                        error: &e,
+                    });
+                    continue;
+                }
             }
-            KODIK_STATE.clear_endpoint();
-            continue;
         }
 
-        if KODIK_STATE.try_begin_update() {
+        rediscovered = true;
+
+        if let Some(update) = KODIK_STATE.try_begin_update() {
             log::warn!("Endpoint not found in cache, updating...");
-            let fetched;
-            let page_html = if html.is_empty() {
-                fetched = scraper::get(client, url).await?;
-                &fetched
-            } else {
-                &html
-            };
-            let player_url = extract_player_url(domain, page_html)?;
-            let player_html = scraper::get(client, &player_url).await?;
-            let new_endpoint = extract_endpoint(&player_html)?;
-            KODIK_STATE.finish_update(new_endpoint);
+            let (new_endpoint, fingerprint) = discover_endpoint(client, url, &html).await?;
+            update.finish(new_endpoint, fingerprint);
             continue;
         }
 
         KODIK_STATE.wait_for_update().await;
     }
 }
+
+/// Finds the API endpoint in the player script linked from the player page.
+///
+/// `html` is the already fetched player page, or empty if it was not needed
+/// so far. Returns the endpoint and the fingerprint of the player build.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "discover_endpoint",
+        level = "debug",
+        skip_all,
+        err,
+        fields(url = %kodik_utils::redact::Redacted(url), elapsed_ms = tracing::field::Empty),
+    )
+)]
+async fn discover_endpoint(
+    client: &impl Transport,
+    url: &str,
+    html: &str,
+) -> Result<(String, Option<String>), Error> {
+    let _timer = trace::Timer::start();
+    let fetched;
+    let page_html = if html.is_empty() {
+        fetched = scraper::get(client, url, Stage::PageFetch).await?;
+        &fetched
+    } else {
+        html
+    };
+    let player_url = extract_player_url(url, page_html)
+        .map_err(|e| availability::or_unavailable(e, Stage::PlayerScript, url, page_html))?;
+    let player_html = scraper::get(client, &player_url, Stage::PlayerScript).await?;
+    let endpoint = extract_endpoint(&player_url, &player_html)?;
+
+    Ok((
+        endpoint,
+        player_fingerprint(&player_url).map(ToOwned::to_owned),
+    ))
+}
@@ -0,0 +1,104 @@
+//! Stable, versioned JSON representation of a parsed video.
+//!
+//! The schema is flat and independent of Kodik's own response layout, so it
+//! stays the same when Kodik reshuffles its API. Version `1` looks like:
+//!
+//! ```json
+//! {
+//!   "schema_version": 1,
+//!   "url": "https://kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p",
+//!   "streams": [
+//!     {
+//!       "quality": 720,
+//!       "kind": "hls",
+//!       "src": "https://p56.kodik.info/s/m/.../720.mp4:hls:manifest.m3u8",
+//!       "mime": "application/x-mpegURL",
+//!       "expires_at": 1755194400
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! - `url` is the player URL the video was parsed from.
+//! - `streams` are sorted by descending `quality`.
+//! - `kind` is one of `hls`, `mp4` or `unknown`.
+//! - `expires_at` is the link expiry as a Unix timestamp, or `null` if unknown.
+//!
+//! Fields are only ever added within a version; renaming or removing one bumps
+//! [`SCHEMA_VERSION`].
+
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Link, Response, StreamKind};
+
+/// Current version of the output schema.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A parsed video in the versioned output schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Video {
+    /// Version of the schema this document follows.
+    pub schema_version: u32,
+    /// Player URL the video was parsed from.
+    pub url: String,
+    /// Available streams, best quality first.
+    pub streams: Vec<Stream>,
+}
+
+/// A single stream of a [`Video`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stream {
+    /// Vertical resolution of the stream.
+    pub quality: u16,
+    /// Delivery format of the stream.
+    pub kind: StreamKind,
+    /// Direct link to the stream.
+    pub src: String,
+    /// MIME type reported by Kodik.
+    pub mime: String,
+    /// Unix timestamp after which the link stops working, if known.
+    pub expires_at: Option<u64>,
+}
+
+impl Video {
+    /// Builds the schema document for a decoded response parsed from `url`.
+    #[must_use]
+    pub fn new(url: &str, response: &Response) -> Self {
+        let links = &response.links;
+        let streams = [
+            (720, &links.quality_720),
+            (480, &links.quality_480),
+            (360, &links.quality_360),
+        ]
+        .into_iter()
+        .flat_map(|(quality, links)| links.iter().map(move |link| Stream::new(quality, link)))
+        .collect();
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            url: url.to_owned(),
+            streams,
+        }
+    }
+}
+
+impl Stream {
+    fn new(quality: u16, link: &Link) -> Self {
+        let expires_at = link
+            .params()
+            .ok()
+            .and_then(|params| params.expires_at())
+            .and_then(|expires_at| expires_at.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs());
+
+        Self {
+            quality,
+            kind: link.kind(),
+            src: link.src.clone(),
+            mime: link.r#type.clone(),
+            expires_at,
+        }
+    }
+}
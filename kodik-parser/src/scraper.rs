@@ -1,19 +1,25 @@
-use crate::parser::VideoInfo;
-use kodik_utils::Error;
+use std::time::Duration;
+
+use crate::{Event, KODIK_STATE, availability, parser::VideoInfo, trace};
+use kodik_utils::{Error, RATE_LIMITER, Stage, redact::Redacted};
 use reqwest::{
     Client,
-    header::{ACCEPT, HeaderName, ORIGIN, REFERER, USER_AGENT},
+    header::{ACCEPT, HeaderMap, HeaderName, HeaderValue, ORIGIN, REFERER},
 };
 use serde::Deserialize;
+#[cfg(feature = "serde")]
+use serde::Serialize;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 /// Response structure for player data containing video links
 pub struct Response {
     /// Available video links organized by quality
     pub links: Links,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 /// Container for video links organized by different quality levels
 pub struct Links {
     /// Video links for 360p quality
@@ -27,7 +33,22 @@ pub struct Links {
     pub quality_720: Vec<Link>,
 }
 
-#[derive(Debug, Deserialize)]
+impl Links {
+    /// Returns the number of links across all qualities.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.quality_360.len() + self.quality_480.len() + self.quality_720.len()
+    }
+
+    /// Returns `true` if there are no links in any quality.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 /// Individual video link with source URL and content type
 pub struct Link {
     /// Source URL of the video stream
@@ -36,52 +57,168 @@ pub struct Link {
     pub r#type: String,
 }
 
-pub async fn get(client: &Client, url: &str) -> Result<String, Error> {
-    let agent = kodik_utils::random_user_agent();
+/// A request of the parse pipeline, built once for every [`Transport`].
+#[derive(Debug)]
+pub struct Request<'a> {
+    pub stage: Stage,
+    pub url: &'a str,
+    pub headers: HeaderMap,
+    /// Form to POST, or `None` for a GET.
+    pub form: Option<&'a VideoInfo<'a>>,
+}
+
+/// Sends the requests of the parse pipeline.
+///
+/// The pipeline is written once over this trait. The async client and the
+/// blocking one only differ in how a request is sent and its body read.
+pub trait Transport: Sync {
+    /// Sends `request` once the rate limiter allows it, retrying after the
+    /// pause a `429 Too Many Requests` asks for, and returns the body.
+    fn send(&self, request: &Request<'_>) -> impl Future<Output = Result<String, Error>> + Send;
+}
+
+impl Transport for Client {
+    async fn send(&self, request: &Request<'_>) -> Result<String, Error> {
+        let Request {
+            stage,
+            url,
+            ref headers,
+            form,
+        } = *request;
+        let build = || {
+            form.map_or_else(|| self.get(url), |form| self.post(url).form(form))
+                .headers(headers.clone())
+        };
+        let response = RATE_LIMITER
+            .send(stage, url, build, retry_scheduled(stage, url))
+            .await?;
 
-    log::info!("GET to {url}...");
+        let status = response.status();
+        trace::record("status", &status.as_u16());
+        let retry_after = kodik_utils::http::retry_after(response.headers());
+        let failure = response.error_for_status_ref().err();
+        let body = response.text().await.map_err(Error::request(stage, url))?;
+
+        availability::check_body(stage, url, status, retry_after, failure, body)
+    }
+}
+
+/// Reports the retries of a rate-limited request to observers.
+pub fn retry_scheduled(stage: Stage, url: &str) -> impl FnMut(u32, Duration) + Send + '_ {
+    move |attempt, pause| {
+        let error = Error::RateLimited {
+            stage,
+            url: url.to_owned(),
+            retry_after: Some(pause),
+            reason: None,
+        };
+        KODIK_STATE.emit(&Event::RetryScheduled {
+            url,
+            attempt,
+            pause,
+            error: &error,
+        });
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "get",
+        level = "debug",
+        skip_all,
+        err,
+        fields(
+            stage = %stage,
+            url = %Redacted(url),
+            status = tracing::field::Empty,
+            elapsed_ms = tracing::field::Empty,
+        ),
+    )
+)]
+pub async fn get(client: &impl Transport, url: &str, stage: Stage) -> Result<String, Error> {
+    let _timer = trace::Timer::start();
+    let headers = kodik_utils::session_headers()?;
+
+    log::info!("GET to {}...", Redacted(url));
 
     let html = client
-        .get(url)
-        .header(USER_AGENT, agent)
-        .send()
-        .await?
-        .text()
+        .send(&Request {
+            stage,
+            url,
+            headers,
+            form: None,
+        })
         .await?;
 
-    log::trace!("Fetched to {url}, response: {html}");
+    log::trace!(
+        "Fetched to {}, response: {}",
+        Redacted(url),
+        Redacted(&html)
+    );
+    KODIK_STATE.emit(&Event::PageFetched { stage, url });
 
     Ok(html)
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "post",
+        level = "debug",
+        skip_all,
+        err,
+        fields(
+            domain = %domain,
+            endpoint = %endpoint,
+            video_id = video_info.id,
+            status = tracing::field::Empty,
+            elapsed_ms = tracing::field::Empty,
+        ),
+    )
+)]
 pub async fn post(
-    client: &Client,
+    client: &impl Transport,
     domain: &str,
     endpoint: &str,
     video_info: &VideoInfo<'_>,
 ) -> Result<Response, Error> {
-    let user_agent = kodik_utils::random_user_agent();
+    let _timer = trace::Timer::start();
+    let mut headers = kodik_utils::session_headers()?;
     let url = format!("https://{domain}{endpoint}");
 
-    log::info!("POST to {url}...");
-
-    let kodik_response = client
-        .post(url)
-        .header(ORIGIN, format!("https://{domain}"))
-        .header(ACCEPT, "application/json, text/javascript, */*; q=0.01")
-        .header(REFERER, format!("https://{domain}"))
-        .header(USER_AGENT, user_agent)
-        .header(
-            HeaderName::from_static("x-requested-with"),
-            "XMLHttpRequest",
-        )
-        .form(&video_info)
-        .send()
-        .await?
-        .json()
+    log::info!("POST to {}...", Redacted(&url));
+
+    let origin =
+        HeaderValue::try_from(format!("https://{domain}")).map_err(|_| Error::InvalidUrl {
+            url: url.clone(),
+            reason: "domain is not a valid header value".to_owned(),
+        })?;
+    headers.insert(ORIGIN, HeaderValue::clone(&origin));
+    headers.insert(
+        ACCEPT,
+        HeaderValue::from_static("application/json, text/javascript, */*; q=0.01"),
+    );
+    headers.insert(REFERER, origin);
+    headers.insert(
+        HeaderName::from_static("x-requested-with"),
+        HeaderValue::from_static("XMLHttpRequest"),
+    );
+
+    let body = client
+        .send(&Request {
+            stage: Stage::Post,
+            url: &url,
+            headers,
+            form: Some(video_info),
+        })
         .await?;
+    let kodik_response: Response = availability::parse_json(Stage::Post, &url, &body)?;
 
-    log::trace!("POST Response: {kodik_response:#?}");
+    log::trace!(
+        "POST Response: {}",
+        Redacted(format_args!("{kodik_response:#?}"))
+    );
 
     Ok(kodik_response)
 }
@@ -0,0 +1,58 @@
+//! Portable copy of [`crate::KODIK_STATE`].
+//!
+//! Endpoint discovery costs two extra requests, so long-lived consumers
+//! persist the state between runs. Take a [`StateSnapshot`] with
+//! [`KodikState::snapshot`](crate::state::KodikState::snapshot), store it
+//! anywhere (with the `serde` feature it serializes to JSON) and hand it back
+//! to [`KodikState::restore`](crate::state::KodikState::restore) on the next
+//! start.
+
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Current version of [`StateSnapshot`]. Snapshots of other versions are
+/// rejected by `restore`.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Everything the parser has learned about Kodik, at a point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StateSnapshot {
+    /// Snapshot format version, see [`SNAPSHOT_VERSION`].
+    pub version: u32,
+    /// Cached API endpoint, empty if unknown.
+    pub endpoint: String,
+    /// Cached shift of the link cipher.
+    pub shift: u8,
+    /// Build hash of the player script the endpoint was found in, if known.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fingerprint: Option<String>,
+    /// What is known about each player domain, by domain name.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub domains: BTreeMap<String, DomainState>,
+    /// When the snapshot was taken, as a Unix timestamp.
+    pub timestamp: u64,
+}
+
+/// What is known about a single player domain.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DomainState {
+    /// Build hash of the player script last served by the domain, if known.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fingerprint: Option<String>,
+    /// When a video was last parsed from the domain, as a Unix timestamp.
+    pub seen_at: u64,
+}
+
+/// Returns the current time as a Unix timestamp.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
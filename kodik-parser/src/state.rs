@@ -1,10 +1,17 @@
-use std::sync::{
-    Arc, LazyLock,
-    atomic::{AtomicBool, AtomicU8, Ordering},
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering},
+    },
 };
 
-use arc_swap::ArcSwap;
-use tokio::sync::Notify;
+use arc_swap::{ArcSwap, ArcSwapOption};
+
+use crate::{
+    observer::{Event, Observer, ObserverId, Registration},
+    snapshot::{self, DomainState, SNAPSHOT_VERSION, StateSnapshot},
+};
 
 pub static KODIK_STATE: LazyLock<KodikState> = LazyLock::new(KodikState::default);
 
@@ -12,8 +19,12 @@ pub static KODIK_STATE: LazyLock<KodikState> = LazyLock::new(KodikState::default
 pub struct KodikState {
     endpoint: ArcSwap<String>,
     shift: AtomicU8,
-    notify: Notify,
+    fingerprint: ArcSwapOption<String>,
+    domains: ArcSwap<BTreeMap<String, DomainState>>,
+    updated: event_listener::Event,
     updating: AtomicBool,
+    observers: ArcSwap<Vec<Registration>>,
+    next_observer: AtomicU64,
 }
 
 impl KodikState {
@@ -22,7 +33,10 @@ impl KodikState {
     }
 
     pub fn set_shift(&self, shift: u8) {
-        self.shift.store(shift, Ordering::Relaxed);
+        let old = self.shift.swap(shift, Ordering::Relaxed);
+        if old != shift {
+            self.emit(&Event::ShiftChanged { old, new: shift });
+        }
     }
 
     pub fn endpoint(&self) -> Arc<String> {
@@ -37,17 +51,168 @@ impl KodikState {
         self.set_endpoint(String::new());
     }
 
-    pub(crate) fn try_begin_update(&self) -> bool {
-        !self.updating.swap(true, Ordering::AcqRel)
+    /// Returns the build hash of the player script the cached endpoint was
+    /// found in, if known.
+    pub fn fingerprint(&self) -> Option<Arc<String>> {
+        self.fingerprint.load_full()
+    }
+
+    /// Records the player build served by `domain` and invalidates the cached
+    /// endpoint if the build differs from the one it was found in.
+    ///
+    /// A domain that keeps serving the build it served before is not treated
+    /// as a change, so domains lagging behind a rollout do not cause repeated
+    /// rediscovery.
+    pub(crate) fn observe_player_build(&self, domain: &str, fingerprint: &str) {
+        let mut previous = None;
+        self.domains.rcu(|domains| {
+            let mut domains = BTreeMap::clone(domains);
+            previous = domains
+                .entry(domain.to_owned())
+                .or_default()
+                .fingerprint
+                .replace(fingerprint.to_owned());
+            domains
+        });
+
+        let Some(current) = self.fingerprint() else {
+            return;
+        };
+        if current.as_str() == fingerprint || previous.as_deref() == Some(fingerprint) {
+            return;
+        }
+
+        log::warn!("Player build changed on {domain}: {current} -> {fingerprint}");
+        self.emit(&Event::PlayerBuildChanged {
+            domain,
+            old: &current,
+            new: fingerprint,
+        });
+        self.fingerprint.store(None);
+        self.clear_endpoint();
+    }
+
+    /// Returns a copy of the state that can be persisted and later passed to
+    /// [`Self::restore`].
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            version: SNAPSHOT_VERSION,
+            endpoint: self.endpoint().to_string(),
+            shift: self.shift(),
+            fingerprint: self.fingerprint.load_full().map(|f| f.to_string()),
+            domains: BTreeMap::clone(&self.domains.load()),
+            timestamp: snapshot::now(),
+        }
+    }
+
+    /// Replaces the state with a snapshot taken earlier.
+    ///
+    /// Returns `false` and leaves the state untouched if the snapshot has a
+    /// different [`StateSnapshot::version`]. Restoring does not notify
+    /// observers.
+    pub fn restore(&self, snapshot: &StateSnapshot) -> bool {
+        if snapshot.version != SNAPSHOT_VERSION {
+            log::warn!(
+                "Ignoring state snapshot version {}, expected {SNAPSHOT_VERSION}",
+                snapshot.version
+            );
+            return false;
+        }
+
+        self.set_endpoint(snapshot.endpoint.clone());
+        self.shift.store(snapshot.shift, Ordering::Relaxed);
+        self.fingerprint
+            .store(snapshot.fingerprint.clone().map(Arc::new));
+        self.domains.store(Arc::new(snapshot.domains.clone()));
+        true
     }
 
-    pub(crate) fn finish_update(&self, endpoint: String) {
-        self.set_endpoint(endpoint);
-        self.updating.store(false, Ordering::Release);
-        self.notify.notify_waiters();
+    /// Records that a video was parsed from `domain` just now.
+    pub(crate) fn touch_domain(&self, domain: &str) {
+        let seen_at = snapshot::now();
+        self.domains.rcu(|domains| {
+            let mut domains = BTreeMap::clone(domains);
+            domains.entry(domain.to_owned()).or_default().seen_at = seen_at;
+            domains
+        });
+    }
+
+    /// Registers an observer for parse progress and state changes.
+    ///
+    /// Observers are global, like the rest of the state: they see events from
+    /// every `parse` call in the process.
+    pub fn subscribe(&self, observer: impl Observer + 'static) -> ObserverId {
+        let id = ObserverId(self.next_observer.fetch_add(1, Ordering::Relaxed));
+        let observer: Arc<dyn Observer> = Arc::new(observer);
+        self.observers.rcu(|observers| {
+            let mut observers = Vec::clone(observers);
+            observers.push(Registration {
+                id,
+                observer: Arc::clone(&observer),
+            });
+            observers
+        });
+        id
+    }
+
+    /// Removes an observer. Returns `false` if it was not registered.
+    pub fn unsubscribe(&self, id: ObserverId) -> bool {
+        let previous = self.observers.rcu(|observers| {
+            let mut observers = Vec::clone(observers);
+            observers.retain(|registration| registration.id != id);
+            observers
+        });
+        previous.iter().any(|registration| registration.id == id)
+    }
+
+    pub(crate) fn emit(&self, event: &Event<'_>) {
+        for registration in self.observers.load().iter() {
+            registration.observer.on_event(event);
+        }
+    }
+
+    /// Claims the right to rediscover the endpoint.
+    ///
+    /// Returns `None` if another task is already updating. Waiters are released
+    /// once the returned guard is finished or dropped, so a failed discovery
+    /// never leaves them hanging.
+    pub(crate) fn try_begin_update(&self) -> Option<UpdateGuard<'_>> {
+        (!self.updating.swap(true, Ordering::AcqRel)).then_some(UpdateGuard { state: self })
     }
 
     pub(crate) async fn wait_for_update(&self) {
-        self.notify.notified().await;
+        let listener = self.updated.listen();
+        if self.updating.load(Ordering::Acquire) {
+            listener.await;
+        }
+    }
+}
+
+/// Exclusive right to update the endpoint, see [`KodikState::try_begin_update`].
+#[derive(Debug)]
+pub struct UpdateGuard<'a> {
+    state: &'a KodikState,
+}
+
+impl UpdateGuard<'_> {
+    /// Caches a newly discovered endpoint together with the fingerprint of
+    /// the player build it was found in.
+    ///
+    /// Both are stored before observers are notified, so a snapshot taken
+    /// on [`Event::EndpointRediscovered`] already holds them.
+    pub(crate) fn finish(self, endpoint: String, fingerprint: Option<String>) {
+        let endpoint = Arc::new(endpoint);
+        self.state.fingerprint.store(fingerprint.map(Arc::new));
+        self.state.endpoint.store(Arc::clone(&endpoint));
+        self.state.emit(&Event::EndpointRediscovered {
+            endpoint: &endpoint,
+        });
+    }
+}
+
+impl Drop for UpdateGuard<'_> {
+    fn drop(&mut self) {
+        self.state.updating.store(false, Ordering::Release);
+        self.state.updated.notify(usize::MAX);
     }
 }
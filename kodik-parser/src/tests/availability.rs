@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use kodik_utils::{Error, Stage, http::error_reason};
+use reqwest::StatusCode;
+
+use crate::{Response, availability};
+
+const URL: &str = "https://kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p";
+
+#[test]
+fn detecting_removed_video() {
+    let html = r#"<html><body><div class="message-block">Видео не найдено
+        или было удалено</div></body></html>"#;
+
+    let error = availability::detect(Stage::PageFetch, URL, StatusCode::OK, None, html).unwrap();
+
+    assert!(matches!(error, Error::VideoRemoved { .. }));
+    assert!(error.is_unavailable());
+    assert!(!error.is_retryable());
+    assert_eq!(Some("Видео не найдено или было удалено"), error.reason());
+    assert_eq!(Some(URL), error.url());
+}
+
+#[test]
+fn detecting_blocked_video() {
+    let geo = r#"<div class="error">Видео недоступно в вашей стране</div>"#;
+    let copyright = r#"<p class="notice">Заблокировано по требованию правообладателя</p>"#;
+
+    let geo = availability::detect(Stage::PageFetch, URL, StatusCode::OK, None, geo).unwrap();
+    let copyright =
+        availability::detect(Stage::PageFetch, URL, StatusCode::OK, None, copyright).unwrap();
+
+    assert!(matches!(geo, Error::GeoBlocked { .. }));
+    assert!(matches!(copyright, Error::CopyrightBlocked { .. }));
+    assert!(geo.is_unavailable() && copyright.is_unavailable());
+}
+
+#[test]
+fn detecting_rate_limit() {
+    let retry_after = Some(Duration::from_secs(30));
+    let error = availability::detect(
+        Stage::Post,
+        URL,
+        StatusCode::TOO_MANY_REQUESTS,
+        retry_after,
+        r#"{"error": "Too many requests"}"#,
+    )
+    .unwrap();
+
+    assert!(matches!(
+        error,
+        Error::RateLimited { retry_after: Some(d), .. } if d == Duration::from_secs(30)
+    ));
+    assert!(error.is_retryable());
+    assert!(!error.is_unavailable());
+    assert_eq!(Some(StatusCode::TOO_MANY_REQUESTS), error.status());
+    assert_eq!(Some("Too many requests"), error.reason());
+}
+
+#[test]
+fn missing_page_is_removed_video_only_for_video_requests() {
+    let not_found = |stage, status| availability::detect(stage, URL, status, None, "Not Found");
+
+    for stage in [Stage::PageFetch, Stage::Post] {
+        assert!(matches!(
+            not_found(stage, StatusCode::NOT_FOUND),
+            Some(Error::VideoRemoved { .. })
+        ));
+        assert!(matches!(
+            not_found(stage, StatusCode::GONE),
+            Some(Error::VideoRemoved { .. })
+        ));
+    }
+    assert!(not_found(Stage::PlayerScript, StatusCode::NOT_FOUND).is_none());
+    assert!(not_found(Stage::Api, StatusCode::GONE).is_none());
+}
+
+#[test]
+fn ignoring_regular_pages() {
+    let html = "<html><body><iframe src=\"//kodikplayer.com/video\"></iframe></body></html>";
+
+    assert!(availability::detect(Stage::PageFetch, URL, StatusCode::OK, None, html).is_none());
+    assert!(error_reason(html).is_none());
+}
+
+#[test]
+fn parsing_json_error_response() {
+    let body = r#"{"error": "Видео было удалено"}"#;
+
+    let error = availability::parse_json::<Response>(Stage::Post, URL, body).unwrap_err();
+
+    assert!(matches!(
+        error,
+        Error::VideoRemoved {
+            stage: Stage::Post,
+            ..
+        }
+    ));
+
+    let error = availability::parse_json::<Response>(Stage::Post, URL, "{}").unwrap_err();
+
+    assert!(matches!(
+        error,
+        Error::Decode {
+            stage: Stage::Post,
+            ..
+        }
+    ));
+}
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use futures_util::StreamExt as _;
+use reqwest::Client;
+
+use crate::{batch::dedup, parse_many};
+
+#[test]
+fn dedup_keeps_first_occurrence_order() {
+    let urls = [
+        "https://kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p",
+        "https://kodikplayer.com/video/115369/2eb2c698195c8a5020284d37dbc981a3/720p",
+        "https://kodik.info/video/91873/060cab655974d46835b3f4405807acc2/360p",
+        "https://kodikplayer.com/video/115369/2eb2c698195c8a5020284d37dbc981a3/720p",
+    ];
+
+    let (jobs, order) = dedup(urls.into_iter().map(ToOwned::to_owned));
+
+    assert_eq!(vec![urls[0], urls[1]], jobs);
+    assert_eq!(vec![0, 1, 0, 1], order);
+}
+
+#[test]
+fn dedup_falls_back_to_url() {
+    let urls = [
+        "https://kodikplayer.com/some-page",
+        " https://kodikplayer.com/some-page ",
+    ];
+
+    let (jobs, order) = dedup(urls.into_iter().map(ToOwned::to_owned));
+
+    assert_eq!(1, jobs.len());
+    assert_eq!(vec![0, 0], order);
+}
+
+#[tokio::test]
+async fn parse_many_yields_one_result_per_url() {
+    let client = Client::new();
+    let urls = ["not a url", "also not a url", "not a url"];
+
+    let results: Vec<_> = parse_many(&client, urls, 2).collect().await;
+
+    assert_eq!(3, results.len());
+    assert!(results.iter().all(Result::is_err));
+    assert!(Arc::ptr_eq(
+        results[0].as_ref().unwrap_err(),
+        results[2].as_ref().unwrap_err()
+    ));
+}
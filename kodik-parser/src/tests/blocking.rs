@@ -0,0 +1,29 @@
+use std::{future::poll_fn, task::Poll, thread};
+
+use reqwest::blocking::Client;
+
+use crate::blocking::{block_on, parse};
+
+#[test]
+#[ignore = "requires network access"]
+fn blocking_parse() {
+    let client = Client::new();
+    let url = "https://kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p";
+    let kodik_response = parse(&client, url).unwrap();
+    println!("{kodik_response:#?}");
+}
+
+#[test]
+fn block_on_waits_for_wake() {
+    let mut woken = false;
+    let output = block_on(poll_fn(|cx| {
+        if woken {
+            return Poll::Ready(42);
+        }
+        woken = true;
+        let waker = cx.waker().clone();
+        thread::spawn(move || waker.wake());
+        Poll::Pending
+    }));
+    assert_eq!(42, output);
+}
@@ -0,0 +1,58 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::{Link, LinkParams};
+
+const SRC: &str = "https://p56.kodik.info/s/m/Ly9jbG91ZC5rb2Rpay1zdG9yYWdlLmNvbS91c2VydXBsb2Fkcy8zOTkyYmZhOS05Yjc3LTQ4ZTItOGZjYS05ZGRmYTg5MzRhODU/15b2259d995c6c5e57d46cf66056066a1162f734b50ca4fc1926aa6f2847c010:2025081421/720.mp4:hls:manifest.m3u8";
+
+#[test]
+fn parsing_link_params() {
+    let params = LinkParams::parse(SRC).unwrap();
+
+    assert_eq!("p56.kodik.info", params.node);
+    assert_eq!(
+        "Ly9jbG91ZC5rb2Rpay1zdG9yYWdlLmNvbS91c2VydXBsb2Fkcy8zOTkyYmZhOS05Yjc3LTQ4ZTItOGZjYS05ZGRmYTg5MzRhODU",
+        params.file_id
+    );
+    assert_eq!(
+        "15b2259d995c6c5e57d46cf66056066a1162f734b50ca4fc1926aa6f2847c010",
+        params.signature
+    );
+    assert_eq!(2_025_081_421, params.expires);
+    assert_eq!(720, params.quality);
+}
+
+#[test]
+fn link_expiry() {
+    let params = LinkParams::parse(SRC).unwrap();
+
+    // 2025-08-14 21:00 MSK is 2025-08-14 18:00 UTC
+    assert_eq!(
+        Some(UNIX_EPOCH + Duration::from_hours(487_554)),
+        params.expires_at()
+    );
+    assert!(params.is_expired());
+}
+
+#[test]
+fn encoded_link_has_no_params() {
+    let link = Link {
+        src: "iPZ0kPU6Tg9eVBGci29siEaciE5ujg9hT20dBPs5iuRPWBNiYhDgGrRAkON5".to_owned(),
+        r#type: "application/x-mpegURL".to_owned(),
+    };
+
+    assert!(link.params().is_err());
+    assert!(link.is_expired());
+}
+
+#[test]
+fn impossible_dates_are_rejected() {
+    let with_expiry = |expires: &str| LinkParams::parse(&SRC.replace("2025081421", expires));
+
+    assert!(with_expiry("2025023100").is_err());
+    assert!(with_expiry("2025022900").is_err());
+    assert!(with_expiry("2025043100").is_err());
+    assert!(with_expiry("2024022900").is_ok());
+    assert!(with_expiry("2000022900").is_ok());
+    assert!(with_expiry("2100022900").is_err());
+    assert!(with_expiry("2025123123").is_ok());
+}
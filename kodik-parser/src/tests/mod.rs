@@ -1,3 +1,15 @@
+mod availability;
+mod batch;
+#[cfg(feature = "blocking")]
+mod blocking;
 mod decoder;
+mod link;
+mod observer;
 mod parser;
+#[cfg(feature = "serde")]
+mod schema;
 mod scraper;
+mod snapshot;
+mod state;
+#[cfg(feature = "tracing")]
+mod trace;
@@ -0,0 +1,99 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{Event, state::KodikState};
+
+fn recording(state: &KodikState) -> (Arc<Mutex<Vec<String>>>, crate::ObserverId) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let id = state.subscribe(move |event: &Event<'_>| {
+        sink.lock().unwrap().push(format!("{event:?}"));
+    });
+    (events, id)
+}
+
+#[test]
+fn shift_changes_are_observed() {
+    let state = KodikState::default();
+    let (events, _) = recording(&state);
+
+    state.set_shift(8);
+    state.set_shift(8);
+    state.set_shift(3);
+
+    assert_eq!(
+        vec![
+            "ShiftChanged { old: 0, new: 8 }",
+            "ShiftChanged { old: 8, new: 3 }"
+        ],
+        *events.lock().unwrap()
+    );
+}
+
+#[test]
+fn rediscovered_endpoint_is_observed() {
+    let state = KodikState::default();
+    let (events, _) = recording(&state);
+
+    state
+        .try_begin_update()
+        .unwrap()
+        .finish("/ftor".to_owned(), None);
+
+    assert_eq!(
+        vec![r#"EndpointRediscovered { endpoint: "/ftor" }"#],
+        *events.lock().unwrap()
+    );
+}
+
+#[test]
+fn snapshot_on_rediscovery_holds_new_endpoint() {
+    let state: &'static KodikState = Box::leak(Box::default());
+    let snapshots = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&snapshots);
+    state.subscribe(move |event: &Event<'_>| {
+        if let Event::EndpointRediscovered { .. } = event {
+            sink.lock().unwrap().push(state.snapshot());
+        }
+    });
+
+    state
+        .try_begin_update()
+        .unwrap()
+        .finish("/ftor".to_owned(), Some("aaaa".to_owned()));
+
+    let snapshots = std::mem::take(&mut *snapshots.lock().unwrap());
+    assert_eq!(1, snapshots.len());
+    assert_eq!("/ftor", snapshots[0].endpoint);
+    assert_eq!(Some("aaaa"), snapshots[0].fingerprint.as_deref());
+}
+
+#[test]
+fn unsubscribed_observer_is_not_called() {
+    let state = KodikState::default();
+    let (events, id) = recording(&state);
+    let (others, _) = recording(&state);
+
+    assert!(state.unsubscribe(id));
+    assert!(!state.unsubscribe(id));
+    state.set_shift(1);
+
+    assert!(events.lock().unwrap().is_empty());
+    assert_eq!(1, others.lock().unwrap().len());
+}
+
+#[test]
+fn player_build_change_is_observed() {
+    let state = KodikState::default();
+    state
+        .try_begin_update()
+        .unwrap()
+        .finish("/ftor".to_owned(), Some("aaaa".to_owned()));
+    let (events, _) = recording(&state);
+
+    state.observe_player_build("kodikplayer.com", "bbbb");
+
+    assert_eq!(
+        vec![r#"PlayerBuildChanged { domain: "kodikplayer.com", old: "aaaa", new: "bbbb" }"#],
+        *events.lock().unwrap()
+    );
+}
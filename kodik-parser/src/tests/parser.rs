@@ -2,7 +2,7 @@ use reqwest::Client;
 
 use crate::{
     parse,
-    parser::{VideoInfo, extract_endpoint, extract_player_url},
+    parser::{VideoInfo, extract_endpoint, extract_player_url, player_fingerprint},
 };
 
 #[test]
@@ -16,7 +16,8 @@ fn v_info_from_response_test() {
    vInfo.id = '91873';
 </script>";
 
-    let video_info = VideoInfo::from_response(html).unwrap();
+    let url = "https://kodikplayer.com/serial/73959/33a2ef3b7cd3a5fd1c1b3ed3c4ac1ecb/720p";
+    let video_info = VideoInfo::from_response(url, html).unwrap();
 
     assert_eq!(expected_video_info, video_info);
 }
@@ -33,7 +34,7 @@ fn v_info_from_url_test() {
 
 #[test]
 fn getting_player_url() {
-    let domain = "kodikplayer.com";
+    let url = "https://kodikplayer.com/serial/73959/33a2ef3b7cd3a5fd1c1b3ed3c4ac1ecb/720p";
     let html = r#"
   </script>
 
@@ -48,17 +49,32 @@ fn getting_player_url() {
   .resume-button { border-radius: 3px; }
   .active-player .resume-button { border-radius: 3px; }"#;
 
-    let player_url = extract_player_url(domain, html).unwrap();
+    let player_url = extract_player_url(url, html).unwrap();
     assert_eq!(
         "https://kodikplayer.com/assets/js/app.player_single.0a909e421830a88800354716d562e21654500844d220805110c7cf2092d70b05.js",
         player_url
     );
 }
 
+#[test]
+fn getting_player_fingerprint() {
+    assert_eq!(
+        Some("0a909e421830a88800354716d562e21654500844d220805110c7cf2092d70b05"),
+        player_fingerprint(
+            "https://kodikplayer.com/assets/js/app.player_single.0a909e421830a88800354716d562e21654500844d220805110c7cf2092d70b05.js"
+        )
+    );
+    assert_eq!(
+        None,
+        player_fingerprint("https://kodikplayer.com/assets/js/app.player_single.js")
+    );
+}
+
 #[test]
 fn getting_endpoint() {
     let player_html = r#"==t.secret&&(e.secret=t.secret),userInfo&&"object"===_typeof(userInfo.info)&&(e.info=JSON.stringify(userInfo.info)),void 0!==window.advertTest&&(e.a_test=!0),!0===t.isUpdate&&(e.isUpdate=!0),$.ajax({type:"POST",url:atob("L2Z0b3I="),"#;
-    assert_eq!("/ftor", extract_endpoint(player_html).unwrap());
+    let url = "https://kodikplayer.com/assets/js/app.player_single.js";
+    assert_eq!("/ftor", extract_endpoint(url, player_html).unwrap());
 }
 
 #[test]
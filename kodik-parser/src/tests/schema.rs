@@ -0,0 +1,62 @@
+use crate::{
+    Link, Links, Response, StreamKind,
+    schema::{SCHEMA_VERSION, Video},
+};
+
+const URL: &str = "https://kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p";
+
+fn decoded_link(quality: u16) -> Link {
+    Link {
+        src: format!(
+            "https://p56.kodik.info/s/m/Ly9jbG91ZC5rb2Rpay1zdG9yYWdlLmNvbS91c2VydXBsb2Fkcy8zOTkyYmZhOS05Yjc3LTQ4ZTItOGZjYS05ZGRmYTg5MzRhODU/15b2259d995c6c5e57d46cf66056066a1162f734b50ca4fc1926aa6f2847c010:2025081421/{quality}.mp4:hls:manifest.m3u8"
+        ),
+        r#type: "application/x-mpegURL".to_owned(),
+    }
+}
+
+fn response() -> Response {
+    Response {
+        links: Links {
+            quality_360: vec![decoded_link(360)],
+            quality_480: vec![decoded_link(480)],
+            quality_720: vec![decoded_link(720)],
+        },
+    }
+}
+
+#[test]
+fn video_from_response() {
+    let video = Video::new(URL, &response());
+
+    assert_eq!(SCHEMA_VERSION, video.schema_version);
+    assert_eq!(URL, video.url);
+    assert_eq!(
+        vec![720, 480, 360],
+        video.streams.iter().map(|s| s.quality).collect::<Vec<_>>()
+    );
+    assert!(video.streams.iter().all(|s| s.kind == StreamKind::Hls));
+    assert_eq!(Some(1_755_194_400), video.streams[0].expires_at);
+}
+
+#[test]
+fn video_serializing() {
+    let video = Video::new(URL, &response());
+    let json = serde_json::to_value(&video).unwrap();
+
+    assert_eq!(1, json["schema_version"]);
+    assert_eq!(URL, json["url"]);
+    assert_eq!(720, json["streams"][0]["quality"]);
+    assert_eq!("hls", json["streams"][0]["kind"]);
+    assert_eq!("application/x-mpegURL", json["streams"][0]["mime"]);
+
+    let roundtrip: Video = serde_json::from_value(json).unwrap();
+    assert_eq!(video, roundtrip);
+}
+
+#[test]
+fn response_roundtrip() {
+    let response = response();
+    let json = serde_json::to_string(&response).unwrap();
+
+    assert_eq!(response, serde_json::from_str::<Response>(&json).unwrap());
+}
@@ -1,9 +1,16 @@
-use std::sync::Arc;
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
 
+use kodik_utils::{Error, Stage};
 use reqwest::Client;
 
 use crate::{
-    Link, Links, Response,
+    Event, KODIK_STATE, Link, Links, Response,
     parser::VideoInfo,
     scraper::{get, post},
 };
@@ -77,7 +84,7 @@ fn kodik_response_deserialization() {
 async fn get_test() {
     let client = Client::new();
     let url = "https://kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p";
-    get(&client, url).await.unwrap();
+    get(&client, url, Stage::PageFetch).await.unwrap();
 }
 
 #[tokio::test]
@@ -89,3 +96,44 @@ async fn post_test() {
     let video_info = VideoInfo::new("video", "060cab655974d46835b3f4405807acc2", "91873");
     post(&client, domain, &endpoint, &video_info).await.unwrap();
 }
+
+#[tokio::test]
+async fn rate_limited_get_schedules_retry() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/page", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for response in [
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+        ] {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+
+    let retries = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&retries);
+    let observed = url.clone();
+    let id = KODIK_STATE.subscribe(move |event: &Event<'_>| {
+        if let Event::RetryScheduled {
+            url,
+            attempt,
+            pause,
+            error,
+        } = *event
+            && url == observed
+        {
+            assert!(matches!(error, Error::RateLimited { .. }));
+            sink.lock().unwrap().push((attempt, pause));
+        }
+    });
+
+    let client = Client::builder().no_proxy().build().unwrap();
+    let body = get(&client, &url, Stage::PageFetch).await.unwrap();
+    KODIK_STATE.unsubscribe(id);
+
+    assert_eq!("ok", body);
+    assert_eq!(vec![(2, Duration::ZERO)], *retries.lock().unwrap());
+}
@@ -0,0 +1,55 @@
+use crate::{SNAPSHOT_VERSION, state::KodikState};
+
+#[test]
+fn snapshot_round_trip() {
+    let state = KodikState::default();
+    state.set_endpoint("/ftor".to_owned());
+    state.set_shift(8);
+    state.touch_domain("kodikplayer.com");
+
+    let snapshot = state.snapshot();
+    assert_eq!(SNAPSHOT_VERSION, snapshot.version);
+    assert_eq!("/ftor", snapshot.endpoint);
+    assert_eq!(8, snapshot.shift);
+    assert!(snapshot.domains["kodikplayer.com"].seen_at > 0);
+    assert!(snapshot.timestamp > 0);
+
+    let restored = KodikState::default();
+    assert!(restored.restore(&snapshot));
+    assert_eq!("/ftor", restored.endpoint().as_str());
+    assert_eq!(8, restored.shift());
+    assert_eq!(snapshot.domains, restored.snapshot().domains);
+}
+
+#[test]
+fn other_versions_are_rejected() {
+    let state = KodikState::default();
+    let mut snapshot = state.snapshot();
+    snapshot.version = SNAPSHOT_VERSION + 1;
+    snapshot.endpoint = "/ftor".to_owned();
+
+    assert!(!state.restore(&snapshot));
+    assert!(state.endpoint().is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn snapshot_serializes() {
+    use crate::StateSnapshot;
+
+    let state = KodikState::default();
+    state.set_endpoint("/ftor".to_owned());
+    let snapshot = state.snapshot();
+
+    let json = serde_json::to_string(&snapshot).unwrap();
+    assert_eq!(
+        snapshot,
+        serde_json::from_str::<StateSnapshot>(&json).unwrap()
+    );
+
+    let minimal: StateSnapshot =
+        serde_json::from_str(r#"{"version":1,"endpoint":"/ftor","shift":3,"timestamp":0}"#)
+            .unwrap();
+    assert_eq!(None, minimal.fingerprint);
+    assert!(minimal.domains.is_empty());
+}
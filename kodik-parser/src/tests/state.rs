@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use crate::state::KodikState;
+
+#[test]
+fn only_one_update_at_a_time() {
+    let state = KodikState::default();
+
+    let update = state.try_begin_update().unwrap();
+    assert!(state.try_begin_update().is_none());
+
+    update.finish("/ftor".to_owned(), None);
+    assert_eq!("/ftor", state.endpoint().as_str());
+    assert!(state.try_begin_update().is_some());
+}
+
+#[test]
+fn failed_update_releases_lock() {
+    let state = KodikState::default();
+
+    drop(state.try_begin_update().unwrap());
+
+    assert!(state.endpoint().is_empty());
+    assert!(state.try_begin_update().is_some());
+}
+
+#[tokio::test]
+async fn waiters_are_woken_by_update() {
+    let state = Arc::new(KodikState::default());
+    let update = state.try_begin_update().unwrap();
+
+    let waiter = {
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            state.wait_for_update().await;
+            state.endpoint()
+        })
+    };
+
+    tokio::task::yield_now().await;
+    update.finish("/ftor".to_owned(), None);
+
+    assert_eq!("/ftor", waiter.await.unwrap().as_str());
+}
+
+#[tokio::test]
+async fn waiting_without_update_returns() {
+    KodikState::default().wait_for_update().await;
+}
+
+#[test]
+fn changed_player_build_drops_endpoint() {
+    let state = KodikState::default();
+    state
+        .try_begin_update()
+        .unwrap()
+        .finish("/ftor".to_owned(), Some("aaaa".to_owned()));
+
+    state.observe_player_build("kodikplayer.com", "aaaa");
+    assert_eq!("/ftor", state.endpoint().as_str());
+
+    state.observe_player_build("kodikplayer.com", "bbbb");
+    assert!(state.endpoint().is_empty());
+    assert_eq!(None, state.fingerprint());
+    assert_eq!(
+        Some("bbbb"),
+        state.snapshot().domains["kodikplayer.com"]
+            .fingerprint
+            .as_deref()
+    );
+}
+
+#[test]
+fn lagging_domain_keeps_endpoint() {
+    let state = KodikState::default();
+    state.observe_player_build("kodik.info", "aaaa");
+    state
+        .try_begin_update()
+        .unwrap()
+        .finish("/ftor".to_owned(), Some("bbbb".to_owned()));
+
+    state.observe_player_build("kodik.info", "aaaa");
+
+    assert_eq!("/ftor", state.endpoint().as_str());
+}
+
+#[test]
+fn unknown_build_keeps_endpoint() {
+    let state = KodikState::default();
+    state.set_endpoint("/ftor".to_owned());
+
+    state.observe_player_build("kodikplayer.com", "aaaa");
+
+    assert_eq!("/ftor", state.endpoint().as_str());
+}
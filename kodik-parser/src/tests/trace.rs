@@ -0,0 +1,91 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use tracing::{
+    Subscriber,
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+};
+use tracing_subscriber::{Layer, layer::Context, prelude::*, registry::LookupSpan};
+
+use crate::{Link, Links, Response, decoder::decode_links};
+
+const SRC: &str = "iPZ0kPU6Tg9eVBGci29siEaciE5ujg9hT20dBPs5iuRPWBNiYhDgGrRAkON5UFxsZht5EDlsjMfbBvHqChsfGhREmEZGYvVqUsHzG3s4ms9Ci3tHjDxwB1UeVDtyGhVUDNM0EtZRlM9PEuxHChI1EslAjDtCHhDVmtRwB0ZDThM1GrQgVBtsWBs1GhHrVEC1V2Y0VuVuVrGeVBGeVrHpUBM2UuG3UhZqVBJrGBZuGhM5UrHpGBHuUro0V2UeUBI6UrIgVBI4UBYgUA8hVrIcjFI0WupakhxbGE5xHuDhlK5bU3C4";
+
+type Spans = Arc<Mutex<HashMap<String, HashMap<String, String>>>>;
+
+/// Collects the fields of every span by span name.
+#[derive(Default, Clone)]
+struct Recorder(Spans);
+
+impl Recorder {
+    fn record(&self, span: &str, record: impl FnOnce(&mut Fields<'_>)) {
+        record(&mut Fields(
+            self.0.lock().unwrap().entry(span.to_owned()).or_default(),
+        ));
+    }
+
+    fn fields(&self, span: &str) -> HashMap<String, String> {
+        self.0.lock().unwrap()[span].clone()
+    }
+}
+
+struct Fields<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_owned(), format!("{value:?}"));
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Recorder {
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        self.record(attrs.metadata().name(), |fields| attrs.record(fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        self.record(ctx.span(id).unwrap().name(), |fields| values.record(fields));
+    }
+}
+
+#[test]
+fn decode_span_records_shift_and_timing() {
+    let recorder = Recorder::default();
+    let subscriber = tracing_subscriber::registry().with(recorder.clone());
+
+    let mut response = Response {
+        links: Links {
+            quality_360: vec![Link {
+                src: SRC.to_owned(),
+                r#type: "application/x-mpegURL".to_owned(),
+            }],
+            quality_480: vec![],
+            quality_720: vec![],
+        },
+    };
+
+    tracing::subscriber::with_default(subscriber, || decode_links(&mut response)).unwrap();
+
+    let decode = recorder.fields("decode");
+    assert_eq!("8", decode["shift"]);
+    assert!(decode.contains_key("elapsed_ms"));
+}
+
+#[tokio::test]
+async fn parse_span_records_url_and_error() {
+    let recorder = Recorder::default();
+    let subscriber = tracing_subscriber::registry().with(recorder.clone());
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let client = reqwest::Client::new();
+    let result = crate::parse(&client, "not a url").await;
+
+    assert!(result.is_err());
+    let parse = recorder.fields("parse");
+    assert_eq!("not a url", parse["url"]);
+    assert_eq!("0", parse["retries"]);
+    assert!(parse.contains_key("elapsed_ms"));
+}
@@ -0,0 +1,54 @@
+//! Helpers for the optional `tracing` instrumentation.
+//!
+//! Without the `tracing` feature every helper compiles to nothing, so call
+//! sites do not need their own `cfg` attributes.
+
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+/// Records the time spent in the current span as its `elapsed_ms` field
+/// when dropped.
+#[cfg(feature = "tracing")]
+#[derive(Debug)]
+pub struct Timer {
+    span: tracing::Span,
+    start: Instant,
+}
+
+#[cfg(feature = "tracing")]
+impl Timer {
+    pub fn start() -> Self {
+        Self {
+            span: tracing::Span::current(),
+            start: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let elapsed = u64::try_from(self.start.elapsed().as_millis()).unwrap_or(u64::MAX);
+        self.span.record("elapsed_ms", elapsed);
+    }
+}
+
+/// Records `value` as `field` of the current span.
+#[cfg(feature = "tracing")]
+pub fn record<T: tracing::Value + ?Sized>(field: &str, value: &T) {
+    tracing::Span::current().record(field, value);
+}
+
+#[cfg(not(feature = "tracing"))]
+#[derive(Debug)]
+pub struct Timer;
+
+#[cfg(not(feature = "tracing"))]
+impl Timer {
+    pub const fn start() -> Self {
+        Self
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub const fn record<T: ?Sized>(_field: &str, _value: &T) {}
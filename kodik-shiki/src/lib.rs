@@ -4,5 +4,10 @@ mod tests;
 
 mod parser;
 mod scraper;
-pub use scraper::run;
-pub use scraper::{TranslationType, VideoResult};
+mod select;
+pub mod token;
+pub use kodik_api::{ExternalId, TranslationType};
+pub use parser::extract_id;
+pub use scraper::VideoResult;
+pub use scraper::{get_kodik_videos, lookup, lookup_with, run, run_with};
+pub use select::{EpisodeLink, EpisodeSelector, SeasonSelector, Selection};
@@ -1,5 +1,11 @@
 use kodik_utils::Error;
 
+/// Extracts the anime id from a Shikimori or `MyAnimeList` URL, e.g. `20` from
+/// `https://shikimori.one/animes/z20-naruto`.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidUrl` if `url` has no anime id.
 pub fn extract_id(url: &str) -> Result<&str, Error> {
     let id_re = lazy_regex::regex!(r"/animes?/(?:[a-z])?([0-9]+)(?:-|$|/)");
 
@@ -7,5 +13,8 @@ pub fn extract_id(url: &str) -> Result<&str, Error> {
         .captures(url)
         .and_then(|caps| caps.get(1))
         .map(|m| m.as_str())
-        .ok_or(Error::RegexMatch(format!("id not found in '{url}'")))
+        .ok_or_else(|| Error::InvalidUrl {
+            url: url.to_owned(),
+            reason: "no anime id found".to_owned(),
+        })
 }
@@ -1,14 +1,18 @@
-use std::{collections::BTreeMap, fmt::Debug};
+use std::fmt::Debug;
 
-use kodik_utils::Error;
+use kodik_api::{ExternalId, KodikApi, Material, Page, TranslationType};
+use kodik_utils::{Error, RATE_LIMITER, Stage, redact::Redacted};
 use lazy_regex::{Regex, regex};
 use reqwest::{
     Client,
-    header::{ACCEPT, COOKIE, HOST, HeaderMap, HeaderValue, USER_AGENT},
+    header::{ACCEPT, COOKIE, HOST, HeaderMap, HeaderValue},
 };
 use serde::{Deserialize, de::DeserializeOwned};
 
-use crate::parser::extract_id;
+use crate::{
+    parser::extract_id,
+    select::{EpisodeLink, EpisodeSelector, SeasonSelector, Selection},
+};
 
 #[derive(Debug, Deserialize)]
 pub struct Response {
@@ -20,40 +24,83 @@ pub struct UserRate {
     episodes: usize,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct SearchResponse {
-    results: Vec<SearchResult>,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-pub struct SearchResult {
-    pub link: String,
-    pub translation: Translation,
-    pub seasons: Option<BTreeMap<usize, Season>>,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-pub struct Translation {
-    pub title: String,
-    pub r#type: TranslationType,
+#[derive(Debug)]
+pub enum VideoResult {
+    Episodes(Vec<EpisodeLink>),
+    Film(String),
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
-#[serde(rename_all = "lowercase")]
-pub enum TranslationType {
-    Voice,
-    Subtitles,
-}
+impl VideoResult {
+    /// Returns the `episodes` of the `season` of `material`, by episode
+    /// number, or the film. Episodes are only known if the material was
+    /// searched for with `with_episodes`.
+    ///
+    /// With [`SeasonSelector::All`], episodes are numbered across the seasons
+    /// in the order they are played, so `episodes` counts from the first
+    /// episode of the first season rather than within each one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::NoSuchSeason` if the material has no such season.
+    pub fn new(
+        material: Material,
+        season: SeasonSelector,
+        episodes: &EpisodeSelector,
+    ) -> Result<Self, Error> {
+        let Some(mut seasons) = material.seasons else {
+            return Ok(Self::Film(material.link));
+        };
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct Season {
-    episodes: BTreeMap<usize, String>,
-}
+        let seasons = match season {
+            SeasonSelector::Latest => seasons.pop_last().into_iter().collect(),
+            SeasonSelector::All => seasons.into_iter().collect(),
+            SeasonSelector::Number(number) => {
+                let Some(season) = seasons.remove(&number) else {
+                    return Err(Error::NoSuchSeason {
+                        season: Some(number),
+                        available: seasons.into_keys().collect(),
+                    });
+                };
+                vec![(number, season)]
+            }
+        };
+        if seasons.is_empty() {
+            return Err(Error::NoSuchSeason {
+                season: None,
+                available: Vec::new(),
+            });
+        }
 
-#[derive(Debug)]
-pub enum VideoResult {
-    Episodes(Vec<String>),
-    Film(String),
+        let all = seasons
+            .into_iter()
+            .flat_map(|(season, s)| {
+                s.episodes
+                    .into_iter()
+                    .map(move |(episode, ep)| EpisodeLink {
+                        season,
+                        episode,
+                        link: ep.into_link(),
+                    })
+            })
+            .collect::<Vec<_>>();
+        let last = all.len().saturating_sub(1);
+        let across_seasons = season == SeasonSelector::All;
+
+        Ok(Self::Episodes(
+            all.into_iter()
+                .enumerate()
+                .filter(|(idx, ep)| {
+                    let number = if across_seasons {
+                        u32::try_from(idx + 1).unwrap_or(u32::MAX)
+                    } else {
+                        ep.episode
+                    };
+                    episodes.contains(number, *idx == last)
+                })
+                .map(|(_, ep)| ep)
+                .collect(),
+        ))
+    }
 }
 
 pub async fn get_user_rate(
@@ -70,52 +117,38 @@ pub async fn get_user_rate(
 }
 
 pub fn find_search_result(
-    mut results: Vec<SearchResult>,
+    mut results: Vec<Material>,
     translation_title: Option<&str>,
     translation_type: Option<&TranslationType>,
-) -> Result<SearchResult, Error> {
+) -> Result<Material, Error> {
+    let not_found = || Error::NoSuchTranslation {
+        title: translation_title.map(ToOwned::to_owned),
+        kind: translation_type.map(|r#type| r#type.as_str().to_owned()),
+    };
+
     if let Some(title) = translation_title {
         let title_re = Regex::new(&format!(r"(?i).*{}.*", regex::escape(title)))?;
 
-        let found_idx = results
+        if let Some(idx) = results
             .iter()
-            .position(|r| title_re.is_match(&r.translation.title));
-
-        match found_idx {
-            Some(idx) => {
-                let result = results.remove(idx);
-                log::info!("Found translation title '{}'", result.translation.title);
-                Ok(result)
-            }
-            None => translation_type.map_or_else(
-                || {
-                    Err(Error::NotFound(format!(
-                        "no video source with title '{title}'"
-                    )))
-                },
-                |r#type| {
-                    results
-                        .into_iter()
-                        .find(|r| r.translation.r#type == *r#type)
-                        .ok_or(Error::NotFound(
-                            "no video source with matching type".to_string(),
-                        ))
-                },
-            ),
+            .position(|r| title_re.is_match(&r.translation.title))
+        {
+            let result = results.remove(idx);
+            log::info!("Found translation title '{}'", result.translation.title);
+            return Ok(result);
         }
-    } else if let Some(search_type) = translation_type {
-        results
-            .into_iter()
-            .find(|r| r.translation.r#type == *search_type)
-            .ok_or(Error::NotFound(
-                "no video source with matching type".to_string(),
-            ))
-    } else {
-        results
+        if translation_type.is_none() {
+            return Err(not_found());
+        }
+    }
+
+    match translation_type {
+        Some(search_type) => results
             .into_iter()
-            .next()
-            .ok_or(Error::NotFound("no video sources found".to_string()))
+            .find(|r| r.translation.r#type == *search_type),
+        None => results.into_iter().next(),
     }
+    .ok_or_else(not_found)
 }
 
 pub async fn get_json<T: DeserializeOwned + Debug>(
@@ -123,20 +156,30 @@ pub async fn get_json<T: DeserializeOwned + Debug>(
     url: &str,
     headers: HeaderMap,
 ) -> Result<T, Error> {
-    let agent = kodik_utils::random_user_agent();
-
-    log::info!("GET to {url}...");
-
-    let resp = client
-        .get(url)
-        .header(USER_AGENT, agent)
-        .headers(headers)
-        .send()
+    let profile = kodik_utils::session_headers()?;
+
+    log::info!("GET to {}...", Redacted(url));
+
+    let build = || {
+        client
+            .get(url)
+            .headers(profile.clone())
+            .headers(headers.clone())
+    };
+    let resp = RATE_LIMITER
+        .send(Stage::Api, url, build, |_, _| {})
         .await?
+        .error_for_status()
+        .map_err(Error::request(Stage::Api, url))?
         .json()
-        .await?;
+        .await
+        .map_err(Error::request(Stage::Api, url))?;
 
-    log::trace!("Fetched to {url}, response: {resp:#?}");
+    log::trace!(
+        "Fetched to {}, response: {}",
+        Redacted(url),
+        Redacted(format_args!("{resp:#?}"))
+    );
 
     Ok(resp)
 }
@@ -156,17 +199,21 @@ fn build_headers(host: &str, with_cookie: Option<&str>) -> Result<HeaderMap, Err
     Ok(headers)
 }
 
-pub async fn get_kodik_videos(client: &Client, id: &str) -> Result<SearchResponse, Error> {
-    let token = env!("KODIK_TOKEN");
-    let url = format!(
-        "https://kodik-api.com/search?token={token}&shikimori_id={id}&with_seasons=true&with_episodes=true"
-    );
-
-    let headers = build_headers("kodik-api.com", None)?;
-    get_json(client, &url, headers).await
+/// Looks up the materials with id `id` on another site with the Kodik API.
+///
+/// # Errors
+///
+/// Returns `KodikError` if the Kodik API request fails.
+pub async fn get_kodik_videos(
+    client: &Client,
+    token: &str,
+    id: &ExternalId,
+) -> Result<Page<Material>, Error> {
+    KodikApi::new(client.clone(), token).lookup(id).await
 }
 
-/// Retrieves video results for an anime from Kodik.
+/// Retrieves video results for an anime from Kodik, searching with the
+/// Kodik API `token`, see [`crate::token::resolve`].
 ///
 /// # Errors
 ///
@@ -177,45 +224,120 @@ pub async fn get_kodik_videos(client: &Client, id: &str) -> Result<SearchRespons
 /// - No matching video source is found
 pub async fn run(
     client: &Client,
+    token: &str,
     url: &str,
     cookie: Option<&str>,
-    translation_title: Option<&str>,
-    translation_type: Option<&TranslationType>,
-    episode: Option<usize>,
+    selection: &Selection<'_>,
 ) -> Result<VideoResult, Error> {
-    let domain = kodik_utils::extract_domain(url)?;
     let id = extract_id(url)?;
+    let search_response =
+        get_kodik_videos(client, token, &ExternalId::Shikimori(id.to_owned())).await?;
 
-    let search_response = get_kodik_videos(client, id).await?;
+    run_with(client, search_response.results, url, cookie, selection).await
+}
 
-    let search_result =
-        find_search_result(search_response.results, translation_title, translation_type)?;
+/// Like [`run`], with the `materials` of the anime already found, e.g. in a
+/// local catalog. Shikimori is still asked for the episodes watched.
+///
+/// # Errors
+///
+/// Returns `KodikError` if:
+/// - The domain cannot be extracted from the URL
+/// - The anime ID cannot be extracted from the URL
+/// - No matching video source is found
+pub async fn run_with(
+    client: &Client,
+    materials: Vec<Material>,
+    url: &str,
+    cookie: Option<&str>,
+    selection: &Selection<'_>,
+) -> Result<VideoResult, Error> {
+    let domain = kodik_utils::extract_domain(url)?;
+    let id = extract_id(url)?;
 
-    if let Some(seasons) = search_result.seasons {
-        let last_episode = if let Some(episode) = episode {
-            episode
-        } else if let Some(cookie) = cookie
-            && let Ok(Some(user_rate)) = get_user_rate(client, domain, id, cookie).await
-        {
-            user_rate.episodes
-        } else {
-            0
-        };
+    let search_result = find_search_result(
+        materials,
+        selection.translation_title,
+        selection.translation_type,
+    )?;
+
+    let watched = if search_result.seasons.is_none() {
+        0
+    } else if let Some(cookie) = cookie
+        && let Ok(Some(user_rate)) = get_user_rate(client, domain, id, cookie).await
+    {
+        user_rate.episodes
+    } else {
+        0
+    };
+    let watched = u32::try_from(watched).unwrap_or(u32::MAX);
+
+    match selection.episodes {
+        Some(episodes) => select(
+            search_result,
+            selection.season,
+            &episodes.clone().unwatched(watched),
+        ),
+        None => VideoResult::new(
+            search_result,
+            selection.season,
+            &EpisodeSelector::after(watched),
+        ),
+    }
+}
 
-        let (_, season) = seasons
-            .into_iter()
-            .next_back()
-            .ok_or(Error::NotFound("no season found".to_string()))?;
+/// Retrieves video results from Kodik for a material identified by its id on
+/// another site, such as `kp:326` or `imdb:tt0111161`.
+///
+/// # Errors
+///
+/// Returns `KodikError` if:
+/// - The Kodik API request fails
+/// - No matching video source is found
+pub async fn lookup(
+    client: &Client,
+    token: &str,
+    id: &ExternalId,
+    selection: &Selection<'_>,
+) -> Result<VideoResult, Error> {
+    let search_response = get_kodik_videos(client, token, id).await?;
 
-        let episodes = season
-            .episodes
-            .into_iter()
-            .skip(last_episode)
-            .map(|(_, ep)| ep)
-            .collect();
+    lookup_with(search_response.results, selection)
+}
 
-        Ok(VideoResult::Episodes(episodes))
-    } else {
-        Ok(VideoResult::Film(search_result.link))
+/// Like [`lookup`], with the `materials` already found, e.g. in a local
+/// catalog.
+///
+/// # Errors
+///
+/// Returns `KodikError` if no matching video source is found.
+pub fn lookup_with(
+    materials: Vec<Material>,
+    selection: &Selection<'_>,
+) -> Result<VideoResult, Error> {
+    let search_result = find_search_result(
+        materials,
+        selection.translation_title,
+        selection.translation_type,
+    )?;
+
+    match selection.episodes {
+        Some(episodes) => select(search_result, selection.season, episodes),
+        None => VideoResult::new(search_result, selection.season, &EpisodeSelector::default()),
+    }
+}
+
+/// Like [`VideoResult::new`], failing if no episode matches `episodes`.
+fn select(
+    material: Material,
+    season: SeasonSelector,
+    episodes: &EpisodeSelector,
+) -> Result<VideoResult, Error> {
+    match VideoResult::new(material, season, episodes)? {
+        VideoResult::Episodes(found) if found.is_empty() => Err(Error::NoSuchEpisode {
+            episodes: episodes.to_string(),
+            season: season.to_string(),
+        }),
+        result => Ok(result),
     }
 }
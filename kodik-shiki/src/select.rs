@@ -0,0 +1,186 @@
+//! Choosing the translation, seasons and episodes to play.
+use std::{fmt, str::FromStr};
+
+use kodik_api::TranslationType;
+use kodik_utils::Error;
+
+/// Seasons of a serial to play.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeasonSelector {
+    /// The highest-numbered season.
+    #[default]
+    Latest,
+    /// Every season in order, specials (season 0) first, with episodes
+    /// numbered across them.
+    All,
+    /// One season by its number.
+    Number(u32),
+}
+
+impl FromStr for SeasonSelector {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "latest" | "last" => Ok(Self::Latest),
+            "all" => Ok(Self::All),
+            n => n.parse().map(Self::Number).map_err(|_| {
+                Error::invalid_argument(s, "expected a season number, 'all' or 'latest'")
+            }),
+        }
+    }
+}
+
+impl fmt::Display for SeasonSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Latest => f.write_str("latest"),
+            Self::All => f.write_str("all"),
+            Self::Number(n) => n.fmt(f),
+        }
+    }
+}
+
+/// Episodes of a season to play, by their numbers.
+///
+/// Parses from and displays as comma-separated parts: `5` for one episode,
+/// `3-7` for a range, `5-` for an episode and all after it, and `last` for
+/// the last episode, e.g. `1,4,9-12`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpisodeSelector {
+    parts: Vec<EpisodePart>,
+    // Episodes up to this one are skipped by open ranges and `last`.
+    watched: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EpisodePart {
+    Range { start: u32, end: Option<u32> },
+    Last,
+}
+
+impl EpisodeSelector {
+    /// Selects the episodes after `episode`, e.g. those not watched yet.
+    #[must_use]
+    pub fn after(episode: u32) -> Self {
+        Self {
+            parts: vec![EpisodePart::Range {
+                start: episode.saturating_add(1),
+                end: None,
+            }],
+            watched: 0,
+        }
+    }
+
+    /// Narrows the open ranges, such as `5-`, and `last` to the episodes
+    /// after `watched`. Single episodes and closed ranges are kept as named.
+    #[must_use]
+    pub const fn unwatched(mut self, watched: u32) -> Self {
+        self.watched = watched;
+        self
+    }
+
+    /// Returns `true` if `episode` is selected, `is_last` telling whether it
+    /// is the last one.
+    #[must_use]
+    pub fn contains(&self, episode: u32, is_last: bool) -> bool {
+        let unwatched = episode > self.watched;
+        self.parts.iter().any(|part| match *part {
+            EpisodePart::Range { start, end: None } => episode >= start && unwatched,
+            EpisodePart::Range {
+                start,
+                end: Some(end),
+            } => episode >= start && episode <= end,
+            EpisodePart::Last => is_last && unwatched,
+        })
+    }
+}
+
+/// Selects every episode.
+impl Default for EpisodeSelector {
+    fn default() -> Self {
+        Self::after(0)
+    }
+}
+
+impl FromStr for EpisodeSelector {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || Error::invalid_argument(s, "expected episodes like 5, 5-, 3-7, 1,4,9-12 or last");
+        let number = |n: &str| n.trim().parse::<u32>().map_err(|_| invalid());
+
+        let parts = s
+            .split(',')
+            .map(|part| {
+                let part = part.trim();
+                if part.eq_ignore_ascii_case("last") {
+                    return Ok(EpisodePart::Last);
+                }
+                let (start, end) = match part.split_once('-') {
+                    Some((start, "")) => (number(start)?, None),
+                    Some((start, end)) => (number(start)?, Some(number(end)?)),
+                    None => {
+                        let episode = number(part)?;
+                        (episode, Some(episode))
+                    }
+                };
+                if end.is_some_and(|end| end < start) {
+                    return Err(invalid());
+                }
+                Ok(EpisodePart::Range { start, end })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { parts, watched: 0 })
+    }
+}
+
+impl fmt::Display for EpisodeSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, part) in self.parts.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(",")?;
+            }
+            match *part {
+                EpisodePart::Range { start, end: None } => write!(f, "{start}-")?,
+                EpisodePart::Range {
+                    start,
+                    end: Some(end),
+                } if start == end => write!(f, "{start}")?,
+                EpisodePart::Range {
+                    start,
+                    end: Some(end),
+                } => write!(f, "{start}-{end}")?,
+                EpisodePart::Last => f.write_str("last")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What to pick among the materials found for a title.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Selection<'a> {
+    /// Part of the translation title, in any case.
+    pub translation_title: Option<&'a str>,
+    /// Voice-overs or subtitles, used if no translation matches the title.
+    pub translation_type: Option<&'a TranslationType>,
+    /// Seasons of a serial.
+    pub season: SeasonSelector,
+    /// Episodes to play. If `None`, those after the episodes watched on
+    /// Shikimori, or all of them. Open ranges and `last` skip the watched
+    /// episodes as well, see [`EpisodeSelector::unwatched`].
+    pub episodes: Option<&'a EpisodeSelector>,
+}
+
+/// An episode of a serial with its season and episode numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpisodeLink {
+    /// Season number, 0 for specials.
+    pub season: u32,
+    /// Episode number within the season.
+    pub episode: u32,
+    /// Player link of the episode.
+    pub link: String,
+}
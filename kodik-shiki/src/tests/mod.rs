@@ -1,2 +1,3 @@
 mod parser;
 mod scraper;
+mod token;
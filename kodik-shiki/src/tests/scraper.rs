@@ -2,9 +2,16 @@ use std::env;
 
 use reqwest::Client;
 
+use kodik_api::{ExternalId, Material, TranslationType};
+use kodik_utils::Error;
+use serde_json::json;
+
 use crate::scraper::{
-    SearchResult, Translation, TranslationType, find_search_result, get_kodik_videos,
-    get_user_rate, run,
+    VideoResult, find_search_result, get_kodik_videos, get_user_rate, lookup, lookup_with, run,
+};
+use crate::{
+    select::{EpisodeLink, EpisodeSelector, SeasonSelector, Selection},
+    token,
 };
 
 #[tokio::test]
@@ -26,59 +33,96 @@ async fn get_user_rate_test() {
 #[tokio::test]
 async fn get_kodik_videos_season_test() {
     let client = Client::new();
+    let token = token::resolve(None).unwrap();
     let id = "467";
 
-    println!("{:#?}", get_kodik_videos(&client, id).await.unwrap());
+    println!(
+        "{:#?}",
+        get_kodik_videos(&client, &token, &ExternalId::Shikimori(id.to_owned()))
+            .await
+            .unwrap()
+    );
 }
 
 #[tokio::test]
 async fn get_kodik_videos_film_test() {
     let client = Client::new();
+    let token = token::resolve(None).unwrap();
     let id = "43";
 
-    println!("{:#?}", get_kodik_videos(&client, id).await.unwrap());
+    println!(
+        "{:#?}",
+        get_kodik_videos(&client, &token, &ExternalId::Shikimori(id.to_owned()))
+            .await
+            .unwrap()
+    );
 }
 
 #[tokio::test]
 async fn run_season_test() {
     let client = Client::new();
+    let token = token::resolve(None).unwrap();
     let url = "https://shikimori.io/animes/467-koukaku-kidoutai-stand-alone-complex";
     println!(
         "{:#?}",
-        run(&client, url, None, None, None, None).await.unwrap()
+        run(&client, &token, url, None, &Selection::default())
+            .await
+            .unwrap()
     );
 }
 
 #[tokio::test]
 async fn run_film_test() {
     let client = Client::new();
+    let token = token::resolve(None).unwrap();
     let url = "https://shikimori.io/animes/43-koukaku-kidoutai";
 
     println!(
         "{:#?}",
-        run(&client, url, None, None, None, None).await.unwrap()
+        run(&client, &token, url, None, &Selection::default())
+            .await
+            .unwrap()
+    );
+}
+
+fn material(title: &str, r#type: &str, link: &str) -> Material {
+    serde_json::from_value(json!({
+        "id": "movie-1",
+        "type": "anime",
+        "link": link,
+        "title": "Ghost in the Shell",
+        "translation": {"id": 1, "title": title, "type": r#type},
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn lookup_test() {
+    let client = Client::new();
+    let token = token::resolve(None).unwrap();
+    let id = "kp:326".parse().unwrap();
+
+    println!(
+        "{:#?}",
+        lookup(&client, &token, &id, &Selection::default())
+            .await
+            .unwrap()
     );
 }
 
 #[test]
 fn find_search_result_test() {
-    let results: [SearchResult; 2] = [
-        SearchResult {
-            link: "//kodikplayer.com/video/54982/9c161034342aff5e14dacd613f21c209/720p".to_owned(),
-            translation: Translation {
-                title: "Reanimedia".to_owned(),
-                r#type: TranslationType::Voice,
-            },
-            seasons: None,
-        },
-        SearchResult {
-            link: "//kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p".to_owned(),
-            translation: Translation {
-                title: "Subtitles".to_owned(),
-                r#type: TranslationType::Subtitles,
-            },
-            seasons: None,
-        },
+    let results = [
+        material(
+            "Reanimedia",
+            "voice",
+            "//kodikplayer.com/video/54982/9c161034342aff5e14dacd613f21c209/720p",
+        ),
+        material(
+            "Subtitles",
+            "subtitles",
+            "//kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p",
+        ),
     ];
 
     let test_cases = [
@@ -101,3 +145,194 @@ fn find_search_result_test() {
         );
     }
 }
+
+#[test]
+fn lookup_with_test() {
+    let link = "//kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p";
+    let materials = vec![
+        material("Reanimedia", "voice", "//kodikplayer.com/video/1/a/720p"),
+        material("Subtitles", "subtitles", link),
+    ];
+
+    let selection = Selection {
+        translation_type: Some(&TranslationType::Subtitles),
+        ..Selection::default()
+    };
+    let result = lookup_with(materials, &selection).unwrap();
+    assert!(matches!(result, VideoResult::Film(film) if film == link));
+    assert!(lookup_with(Vec::new(), &Selection::default()).is_err());
+}
+
+fn serial(seasons: &[(u32, &[u32])]) -> Material {
+    let seasons = seasons
+        .iter()
+        .map(|(season, episodes)| {
+            let episodes = episodes
+                .iter()
+                .map(|ep| {
+                    (
+                        ep.to_string(),
+                        json!(format!("//kodik.info/seria/{season}-{ep}")),
+                    )
+                })
+                .collect::<serde_json::Map<_, _>>();
+            (season.to_string(), json!({"episodes": episodes}))
+        })
+        .collect::<serde_json::Map<_, _>>();
+    serde_json::from_value(json!({
+        "id": "serial-1",
+        "type": "anime-serial",
+        "link": "//kodik.info/serial/1/abc/720p",
+        "title": "Ghost in the Shell",
+        "translation": {"id": 1, "title": "Reanimedia", "type": "voice"},
+        "seasons": seasons,
+    }))
+    .unwrap()
+}
+
+fn episodes(result: VideoResult) -> Vec<(u32, u32)> {
+    match result {
+        VideoResult::Episodes(episodes) => episodes
+            .into_iter()
+            .map(
+                |EpisodeLink {
+                     season, episode, ..
+                 }| (season, episode),
+            )
+            .collect(),
+        VideoResult::Film(_) => Vec::new(),
+    }
+}
+
+#[test]
+fn season_selector_test() {
+    let material = serial(&[(0, &[1]), (1, &[1, 2]), (2, &[1, 2, 3])]);
+    let select = |season, selector: &str| {
+        VideoResult::new(material.clone(), season, &selector.parse().unwrap()).map(episodes)
+    };
+
+    assert_eq!(
+        vec![(2, 1), (2, 2), (2, 3)],
+        select(SeasonSelector::Latest, "1-").unwrap()
+    );
+    assert_eq!(vec![(2, 3)], select(SeasonSelector::Latest, "3-").unwrap());
+    assert_eq!(
+        vec![(1, 1), (1, 2)],
+        select(SeasonSelector::Number(1), "1-").unwrap()
+    );
+    assert_eq!(
+        vec![(0, 1)],
+        select(SeasonSelector::Number(0), "1-").unwrap()
+    );
+    assert_eq!(
+        vec![(0, 1), (1, 1), (1, 2), (2, 1), (2, 2), (2, 3)],
+        select(SeasonSelector::All, "1-").unwrap()
+    );
+    assert!(select(SeasonSelector::Number(3), "1-").is_err());
+
+    // Across seasons, episodes are counted from the first one played.
+    assert_eq!(
+        vec![(1, 2), (2, 1), (2, 2), (2, 3)],
+        select(SeasonSelector::All, "3-").unwrap()
+    );
+    assert_eq!(vec![(1, 1)], select(SeasonSelector::All, "2").unwrap());
+    assert_eq!(vec![(2, 3)], select(SeasonSelector::All, "last").unwrap());
+
+    assert_eq!(
+        Some("//kodik.info/seria/1-2"),
+        match VideoResult::new(
+            material,
+            SeasonSelector::Number(1),
+            &EpisodeSelector::after(1)
+        )
+        .unwrap()
+        {
+            VideoResult::Episodes(episodes) => episodes.first().map(|ep| ep.link.clone()),
+            VideoResult::Film(_) => None,
+        }
+        .as_deref()
+    );
+}
+
+#[test]
+fn parse_season_selector_test() {
+    assert_eq!(SeasonSelector::Latest, "latest".parse().unwrap());
+    assert_eq!(SeasonSelector::All, "ALL".parse().unwrap());
+    assert_eq!(SeasonSelector::Number(0), "0".parse().unwrap());
+    assert!(matches!(
+        "first".parse::<SeasonSelector>(),
+        Err(Error::InvalidArgument { .. })
+    ));
+    assert_eq!("2", SeasonSelector::Number(2).to_string());
+}
+
+#[test]
+fn episode_selector_test() {
+    let material = serial(&[(1, &[2, 3, 4, 5, 7])]);
+    let select = |selector: &str| {
+        let selector = selector.parse().unwrap();
+        episodes(VideoResult::new(material.clone(), SeasonSelector::Latest, &selector).unwrap())
+            .into_iter()
+            .map(|(_, episode)| episode)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(vec![5], select("5"));
+    assert_eq!(vec![3, 4, 5], select("3-5"));
+    assert_eq!(vec![5, 7], select("5-"));
+    assert_eq!(vec![4], select("1,4,9-12"));
+    assert_eq!(vec![7], select("last"));
+    assert_eq!(vec![2, 7], select("2, LAST"));
+    assert!(select("6").is_empty());
+
+    let unwatched = |selector: &str, watched| {
+        let selector = selector
+            .parse::<EpisodeSelector>()
+            .unwrap()
+            .unwatched(watched);
+        episodes(VideoResult::new(material.clone(), SeasonSelector::Latest, &selector).unwrap())
+            .into_iter()
+            .map(|(_, episode)| episode)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec![5, 7], unwatched("3-", 4));
+    assert_eq!(vec![3, 4, 5], unwatched("3-5", 4));
+    assert_eq!(vec![2], unwatched("2", 4));
+    assert_eq!(vec![7], unwatched("last", 5));
+    assert!(unwatched("last", 7).is_empty());
+
+    let selector = "6".parse().unwrap();
+    let selection = Selection {
+        episodes: Some(&selector),
+        ..Selection::default()
+    };
+    assert!(lookup_with(vec![material.clone()], &selection).is_err());
+    let selection = Selection::default();
+    assert_eq!(
+        vec![(1, 2), (1, 3), (1, 4), (1, 5), (1, 7)],
+        episodes(lookup_with(vec![material], &selection).unwrap())
+    );
+}
+
+#[test]
+fn parse_episode_selector_test() {
+    let selector: EpisodeSelector = "1,4, 9-12,last,20-".parse().unwrap();
+    assert_eq!("1,4,9-12,last,20-", selector.to_string());
+    assert!(selector.contains(10, false));
+    assert!(selector.contains(25, false));
+    assert!(selector.contains(13, true));
+    assert!(!selector.contains(13, false));
+
+    assert_eq!("3-", EpisodeSelector::after(2).to_string());
+    assert_eq!("1-", EpisodeSelector::default().to_string());
+
+    for invalid in ["", "a", "7-3", "-3", "1-2-3", "1,,2"] {
+        assert!(
+            matches!(
+                invalid.parse::<EpisodeSelector>(),
+                Err(Error::InvalidArgument { .. })
+            ),
+            "{invalid}"
+        );
+    }
+}
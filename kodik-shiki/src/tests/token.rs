@@ -0,0 +1,15 @@
+use crate::token::{from_env, resolve};
+
+#[test]
+fn resolve_test() {
+    assert_eq!("abc", resolve(Some(" abc ")).unwrap());
+
+    // A blank token falls back to the environment.
+    match from_env() {
+        Some(env_token) => assert_eq!(env_token, resolve(Some("  ")).unwrap()),
+        None if cfg!(not(feature = "embedded-token")) => {
+            assert!(resolve(None).unwrap_err().is_user_error());
+        }
+        None => {}
+    }
+}
@@ -0,0 +1,50 @@
+//! Kodik API token lookup.
+//!
+//! The token is taken at runtime: passed by the caller or read from the
+//! [`TOKEN_VAR`] environment variable. Builds with the `embedded-token`
+//! feature also fall back to the value `KODIK_TOKEN` had at compile time.
+use std::env;
+
+use kodik_utils::Error;
+
+/// Environment variable holding the Kodik API token.
+pub const TOKEN_VAR: &str = "KODIK_TOKEN";
+
+/// Returns the token from the [`TOKEN_VAR`] environment variable, if set and
+/// not blank.
+#[must_use]
+pub fn from_env() -> Option<String> {
+    env::var(TOKEN_VAR)
+        .ok()
+        .map(|token| token.trim().to_owned())
+        .filter(|token| !token.is_empty())
+}
+
+/// Returns `token` if given and not blank, otherwise the token from the
+/// environment, otherwise the one embedded at compile time.
+///
+/// # Errors
+///
+/// Returns `Error::MissingToken` if no token is found.
+pub fn resolve(token: Option<&str>) -> Result<String, Error> {
+    token
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .map(str::to_owned)
+        .or_else(from_env)
+        .or_else(embedded)
+        .ok_or(Error::MissingToken)
+}
+
+#[cfg(feature = "embedded-token")]
+fn embedded() -> Option<String> {
+    option_env!("KODIK_TOKEN")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .map(str::to_owned)
+}
+
+#[cfg(not(feature = "embedded-token"))]
+const fn embedded() -> Option<String> {
+    None
+}
@@ -0,0 +1,140 @@
+//! Cookie jar shared by a client and kept on disk between runs.
+//!
+//! Cookies set by servers, such as a refreshed Shikimori `_kawai_session`,
+//! land in the jar of a client built with
+//! [`ClientBuilder::cookie_jar`](crate::http::ClientBuilder::cookie_jar).
+//! [`CookieJar::save`] writes them to a JSON file, including session cookies,
+//! and [`CookieJar::load`] reads them back.
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter},
+    path::Path,
+    sync::{Arc, MutexGuard, PoisonError},
+};
+
+use cookie_store::{CookieStore, RawCookie};
+use reqwest::Url;
+use reqwest_cookie_store::CookieStoreMutex;
+
+use crate::Error;
+
+/// Persistent cookie store, cheap to clone.
+#[derive(Debug, Clone, Default)]
+pub struct CookieJar {
+    store: Arc<CookieStoreMutex>,
+}
+
+impl CookieJar {
+    /// Creates an empty jar.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a jar saved with [`Self::save`], skipping expired cookies. A
+    /// missing file gives an empty jar.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::CookieJar` if the file cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(Error::cookie_jar(path)(e)),
+        };
+        let store = cookie_store::serde::json::load(BufReader::new(file))
+            .map_err(Error::cookie_jar(path))?;
+
+        Ok(Self {
+            store: Arc::new(CookieStoreMutex::new(store)),
+        })
+    }
+
+    /// Writes every cookie to `path`, creating its directory if needed.
+    /// Session cookies are kept, since sites like Shikimori use them for
+    /// logins, so on Unix the file is only readable by the current user.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::CookieJar` if the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(Error::cookie_jar(path))?;
+        }
+        let mut writer = BufWriter::new(create_private(path).map_err(Error::cookie_jar(path))?);
+        cookie_store::serde::json::save_incl_expired_and_nonpersistent(&self.lock(), &mut writer)
+            .map_err(Error::cookie_jar(path))
+    }
+
+    /// Adds the cookies of a `Cookie` header value, `name=value; ...`, as if
+    /// `url` had set them. Malformed pairs are skipped.
+    pub fn add_header(&self, cookies: &str, url: &Url) {
+        let mut store = self.lock();
+        for pair in cookies
+            .split(';')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+        {
+            match RawCookie::parse(pair) {
+                Ok(cookie) => {
+                    if let Err(e) = store.insert_raw(&cookie, url) {
+                        log::debug!("Skipping cookie '{}': {e}", cookie.name());
+                    }
+                }
+                Err(e) => log::debug!("Skipping malformed cookie: {e}"),
+            }
+        }
+    }
+
+    /// Returns the `Cookie` header value a request to `url` would carry, or
+    /// `None` if no cookie matches.
+    #[must_use]
+    pub fn header(&self, url: &Url) -> Option<String> {
+        let header = self
+            .lock()
+            .get_request_values(url)
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+        (!header.is_empty()).then_some(header)
+    }
+
+    /// Returns the value of the cookie `name` sent to `url`, if any.
+    #[must_use]
+    pub fn get(&self, url: &Url, name: &str) -> Option<String> {
+        self.lock()
+            .get_request_values(url)
+            .find(|(cookie, _)| *cookie == name)
+            .map(|(_, value)| value.to_owned())
+    }
+
+    /// Returns `true` if the jar holds no cookies.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().iter_any().next().is_none()
+    }
+
+    pub(crate) fn provider(&self) -> Arc<CookieStoreMutex> {
+        Arc::clone(&self.store)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CookieStore> {
+        self.store.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Creates or truncates `path`, readable only by the current user on Unix.
+fn create_private(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let file = options.open(path)?;
+    // The mode only applies to a new file, an older one may be readable.
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    Ok(file)
+}
@@ -0,0 +1,102 @@
+//! Resolving hostnames through chosen nameservers instead of the system ones.
+//!
+//! Some ISPs poison DNS answers for Kodik domains. [`Nameservers`] sends
+//! queries straight to the given servers, over UDP with a TCP fallback.
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, OnceLock},
+};
+
+use hickory_resolver::{
+    TokioResolver,
+    config::{LookupIpStrategy, NameServerConfig, NameServerConfigGroup, ResolverConfig},
+    name_server::TokioConnectionProvider,
+    proto::xfer::Protocol,
+};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+use crate::Error;
+
+/// DNS resolver querying a fixed list of nameservers, usable with
+/// [`reqwest::ClientBuilder::dns_resolver`].
+#[derive(Debug, Clone)]
+pub struct Nameservers {
+    servers: Vec<SocketAddr>,
+    // Built on first use, when a tokio runtime is sure to be running.
+    resolver: Arc<OnceLock<TokioResolver>>,
+}
+
+impl Nameservers {
+    /// Creates a resolver querying `servers` in order.
+    #[must_use]
+    pub fn new(servers: Vec<SocketAddr>) -> Self {
+        Self {
+            servers,
+            resolver: Arc::default(),
+        }
+    }
+
+    /// Returns the nameservers queried.
+    #[must_use]
+    pub fn servers(&self) -> &[SocketAddr] {
+        &self.servers
+    }
+
+    fn resolver(&self) -> &TokioResolver {
+        self.resolver.get_or_init(|| {
+            let group: NameServerConfigGroup = self
+                .servers
+                .iter()
+                .flat_map(|&server| {
+                    [
+                        NameServerConfig::new(server, Protocol::Udp),
+                        NameServerConfig::new(server, Protocol::Tcp),
+                    ]
+                })
+                .collect::<Vec<_>>()
+                .into();
+            let config = ResolverConfig::from_parts(None, Vec::new(), group);
+
+            let mut builder =
+                TokioResolver::builder_with_config(config, TokioConnectionProvider::default());
+            builder.options_mut().ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+            builder.build()
+        })
+    }
+}
+
+impl Resolve for Nameservers {
+    fn resolve(&self, name: Name) -> Resolving {
+        let this = self.clone();
+        Box::pin(async move {
+            let lookup = this.resolver().lookup_ip(name.as_str()).await?;
+            let addrs: Addrs = Box::new(
+                lookup
+                    .into_iter()
+                    .map(|ip| SocketAddr::new(ip, 0))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            );
+            Ok(addrs)
+        })
+    }
+}
+
+/// Parses a nameserver address: an IP address, optionally with a port, e.g.
+/// `1.1.1.1`, `1.1.1.1:5353` or `[2606:4700:4700::1111]:53`.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidArgument` if `server` is not an IP address.
+pub fn parse_nameserver(server: &str) -> Result<SocketAddr, Error> {
+    let server = server.trim();
+    server
+        .parse::<SocketAddr>()
+        .or_else(|_| server.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+        .map_err(|_| {
+            Error::invalid_argument(
+                server,
+                "expected a nameserver IP address, optionally with a port",
+            )
+        })
+}
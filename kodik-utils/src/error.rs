@@ -1,40 +1,441 @@
 //! Error types for the Kodik library.
-use reqwest::header;
-use std::string;
+use std::{error::Error as StdError, fmt, time::Duration};
+
+use reqwest::{StatusCode, header};
 use thiserror::Error as ThisError;
 
+use crate::redact::Redacted;
+
+/// Boxed error used as the source of decoding failures.
+pub type BoxError = Box<dyn StdError + Send + Sync>;
+
+/// Stage of the pipeline an error occurred in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Stage {
+    /// Validating the input URL.
+    Url,
+    /// Fetching the player page.
+    PageFetch,
+    /// Extracting video info from the player page.
+    VideoInfo,
+    /// Fetching the player script.
+    PlayerScript,
+    /// Discovering the API endpoint in the player script.
+    Endpoint,
+    /// Posting video info to the API endpoint.
+    Post,
+    /// Decoding the links.
+    Decode,
+    /// Calling the Kodik or Shikimori API.
+    Api,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Url => "url",
+            Self::PageFetch => "page fetch",
+            Self::VideoInfo => "video info",
+            Self::PlayerScript => "player script",
+            Self::Endpoint => "endpoint",
+            Self::Post => "post",
+            Self::Decode => "decode",
+            Self::Api => "api",
+        })
+    }
+}
+
 /// Errors from kodik.
 #[derive(ThisError, Debug)]
 #[non_exhaustive]
 pub enum Error {
-    /// Reqwest HTTP client error.
-    #[error("{0}")]
-    Reqwest(#[from] reqwest::Error),
+    /// The URL is malformed or does not point to a supported page.
+    #[error("invalid url '{}': {}", Redacted(.url), Redacted(.reason))]
+    InvalidUrl {
+        /// URL given by the caller.
+        url: String,
+        /// What is wrong with it.
+        reason: String,
+    },
 
-    /// Base64 decoding error.
-    #[error("{0}")]
-    Decode(#[from] base64::DecodeError),
+    /// A value given by the caller, such as a command-line option, is
+    /// malformed.
+    #[error("invalid value '{value}': {reason}")]
+    InvalidArgument {
+        /// Value given by the caller.
+        value: String,
+        /// What is wrong with it.
+        reason: String,
+    },
 
-    /// UTF-8 conversion error.
-    #[error("{0}")]
-    FromUtf8(#[from] string::FromUtf8Error),
+    /// HTTP request failed or returned an error status.
+    #[error("{stage}: request to '{}' failed", Redacted(.url))]
+    Request {
+        /// Stage the request was made in.
+        stage: Stage,
+        /// Requested URL.
+        url: String,
+        /// Underlying reqwest error.
+        #[source]
+        source: reqwest::Error,
+    },
 
-    /// Regex matching error.
-    #[error("{0}")]
-    RegexMatch(String),
+    /// Response does not have the expected shape, most likely because Kodik
+    /// changed its pages.
+    #[error("{stage}: unexpected response from '{}': {}", Redacted(.url), Redacted(.reason))]
+    UnexpectedResponse {
+        /// Stage the response was processed in.
+        stage: Stage,
+        /// URL the response came from.
+        url: String,
+        /// What was expected but not found.
+        reason: String,
+    },
 
-    /// Link cannot be decoded error.
-    #[error("link cannot be decoded {0}")]
+    /// Data in a response cannot be decoded.
+    #[error("{stage}: cannot decode data from '{}'", Redacted(.url))]
+    Decode {
+        /// Stage the data was decoded in.
+        stage: Stage,
+        /// URL the data came from.
+        url: String,
+        /// Underlying decoding error.
+        #[source]
+        source: BoxError,
+    },
+
+    /// Video was removed from Kodik.
+    #[error("video at '{}' was removed{}", Redacted(.url), Reason(.reason.as_deref()))]
+    VideoRemoved {
+        /// Stage the removal was detected in.
+        stage: Stage,
+        /// URL of the video.
+        url: String,
+        /// Explanation supplied by Kodik, if any.
+        reason: Option<String>,
+    },
+
+    /// Video is not available in the client's country.
+    #[error("video at '{}' is blocked in your country{}", Redacted(.url), Reason(.reason.as_deref()))]
+    GeoBlocked {
+        /// Stage the block was detected in.
+        stage: Stage,
+        /// URL of the video.
+        url: String,
+        /// Explanation supplied by Kodik, if any.
+        reason: Option<String>,
+    },
+
+    /// Video is blocked at the request of the copyright holder.
+    #[error(
+        "video at '{}' is blocked by the copyright holder{}",
+        Redacted(.url),
+        Reason(.reason.as_deref())
+    )]
+    CopyrightBlocked {
+        /// Stage the block was detected in.
+        stage: Stage,
+        /// URL of the video.
+        url: String,
+        /// Explanation supplied by Kodik, if any.
+        reason: Option<String>,
+    },
+
+    /// Server refused the request because too many were made.
+    #[error("{stage}: rate limited by '{}'{}", Redacted(.url), Reason(.reason.as_deref()))]
+    RateLimited {
+        /// Stage the request was made in.
+        stage: Stage,
+        /// Requested URL.
+        url: String,
+        /// How long the server asked to wait, if it said so.
+        retry_after: Option<Duration>,
+        /// Explanation supplied by the server, if any.
+        reason: Option<String>,
+    },
+
+    /// HTTP client cannot be built.
+    #[error("cannot build http client")]
+    Client(#[source] reqwest::Error),
+
+    /// Cookie jar file cannot be read or written.
+    #[error("cannot access cookie jar '{path}'")]
+    CookieJar {
+        /// Path of the jar file.
+        path: String,
+        /// Underlying I/O or parsing error.
+        #[source]
+        source: BoxError,
+    },
+
+    /// Local catalog database cannot be read or written.
+    #[error("catalog database error")]
+    Catalog(#[source] BoxError),
+
+    /// Link cannot be decoded with any shift.
+    #[error("link cannot be decoded {}", Redacted(.0))]
     LinkCannotBeDecoded(String),
 
     /// Invaliad header value
     #[error("{0}")]
     InvalidHeaderValue(#[from] header::InvalidHeaderValue),
 
-    /// Not found error.
-    #[error("{0}")]
-    NotFound(String),
+    /// No Kodik API token was given or found in the environment.
+    #[error("Kodik API token is not set, pass one or set KODIK_TOKEN")]
+    MissingToken,
+
+    /// No translation of the material matches the title or type asked for,
+    /// or the material has none at all.
+    #[error("{}", Translation(.title.as_deref(), .kind.as_deref()))]
+    NoSuchTranslation {
+        /// Part of the translation title asked for.
+        title: Option<String>,
+        /// Translation type asked for, such as `voice`.
+        kind: Option<String>,
+    },
+
+    /// The serial has no such season, or no seasons at all.
+    #[error("{}", Season(*.season, .available))]
+    NoSuchSeason {
+        /// Season asked for, `None` for the latest one.
+        season: Option<u32>,
+        /// Seasons the serial has.
+        available: Vec<u32>,
+    },
+
+    /// None of the episodes asked for exists in the season.
+    #[error("no episode {episodes} in season {season}")]
+    NoSuchEpisode {
+        /// Episodes asked for, as given.
+        episodes: String,
+        /// Season looked in, as given.
+        season: String,
+    },
 
+    /// A regular expression cannot be built. This is a bug.
     #[error("{0}")]
     Regex(#[from] lazy_regex::regex::Error),
 }
+
+impl Error {
+    /// Returns a closure wrapping a reqwest error made at `stage` for `url`.
+    pub fn request(stage: Stage, url: &str) -> impl FnOnce(reqwest::Error) -> Self {
+        // The URL is kept in `url`, where it is redacted when displayed.
+        move |source| Self::Request {
+            stage,
+            url: url.to_owned(),
+            source: source.without_url(),
+        }
+    }
+
+    /// Creates an [`Error::InvalidArgument`].
+    pub fn invalid_argument(value: &str, reason: impl Into<String>) -> Self {
+        Self::InvalidArgument {
+            value: value.to_owned(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Creates an [`Error::UnexpectedResponse`].
+    pub fn unexpected(stage: Stage, url: &str, reason: impl Into<String>) -> Self {
+        Self::UnexpectedResponse {
+            stage,
+            url: url.to_owned(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Returns a closure wrapping a decoding error made at `stage` for `url`.
+    pub fn decode<E: Into<BoxError>>(stage: Stage, url: &str) -> impl FnOnce(E) -> Self {
+        move |source| Self::Decode {
+            stage,
+            url: url.to_owned(),
+            source: source.into(),
+        }
+    }
+
+    /// Returns a closure wrapping an I/O or parsing error of the cookie jar
+    /// at `path`.
+    pub fn cookie_jar<E: Into<BoxError>>(path: &std::path::Path) -> impl FnOnce(E) -> Self {
+        let path = path.display().to_string();
+        move |source| Self::CookieJar {
+            path,
+            source: source.into(),
+        }
+    }
+
+    /// Wraps an error of the local catalog database.
+    pub fn catalog<E: Into<BoxError>>(source: E) -> Self {
+        Self::Catalog(source.into())
+    }
+
+    /// Returns the stage the error occurred in, if known.
+    #[must_use]
+    pub const fn stage(&self) -> Option<Stage> {
+        match self {
+            Self::InvalidUrl { .. } => Some(Stage::Url),
+            Self::Request { stage, .. }
+            | Self::UnexpectedResponse { stage, .. }
+            | Self::Decode { stage, .. }
+            | Self::VideoRemoved { stage, .. }
+            | Self::GeoBlocked { stage, .. }
+            | Self::CopyrightBlocked { stage, .. }
+            | Self::RateLimited { stage, .. } => Some(*stage),
+            Self::LinkCannotBeDecoded(_) => Some(Stage::Decode),
+            Self::MissingToken => Some(Stage::Api),
+            Self::InvalidArgument { .. }
+            | Self::Client(_)
+            | Self::CookieJar { .. }
+            | Self::Catalog(_)
+            | Self::InvalidHeaderValue(_)
+            | Self::NoSuchTranslation { .. }
+            | Self::NoSuchSeason { .. }
+            | Self::NoSuchEpisode { .. }
+            | Self::Regex(_) => None,
+        }
+    }
+
+    /// Returns the URL involved in the error, if any.
+    #[must_use]
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Self::InvalidUrl { url, .. }
+            | Self::Request { url, .. }
+            | Self::UnexpectedResponse { url, .. }
+            | Self::Decode { url, .. }
+            | Self::VideoRemoved { url, .. }
+            | Self::GeoBlocked { url, .. }
+            | Self::CopyrightBlocked { url, .. }
+            | Self::RateLimited { url, .. }
+            | Self::LinkCannotBeDecoded(url) => Some(url),
+            Self::InvalidArgument { .. }
+            | Self::Client(_)
+            | Self::CookieJar { .. }
+            | Self::Catalog(_)
+            | Self::MissingToken
+            | Self::InvalidHeaderValue(_)
+            | Self::NoSuchTranslation { .. }
+            | Self::NoSuchSeason { .. }
+            | Self::NoSuchEpisode { .. }
+            | Self::Regex(_) => None,
+        }
+    }
+
+    /// Returns the HTTP status of a failed request, if any.
+    #[must_use]
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Request { source, .. } => source.status(),
+            Self::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            _ => None,
+        }
+    }
+
+    /// Returns the explanation supplied by Kodik for an unavailable video or
+    /// a rate limit, if any.
+    #[must_use]
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            Self::VideoRemoved { reason, .. }
+            | Self::GeoBlocked { reason, .. }
+            | Self::CopyrightBlocked { reason, .. }
+            | Self::RateLimited { reason, .. } => reason.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the video itself cannot be played: it was removed or
+    /// is blocked. Retrying will not help, but other videos are unaffected.
+    #[must_use]
+    pub const fn is_unavailable(&self) -> bool {
+        matches!(
+            self,
+            Self::VideoRemoved { .. } | Self::GeoBlocked { .. } | Self::CopyrightBlocked { .. }
+        )
+    }
+
+    /// Returns `true` if the same call may succeed when retried later:
+    /// timeouts, connection failures, rate limiting and server errors.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Request { source, .. } => {
+                source.is_timeout()
+                    || source.is_connect()
+                    || source.status().is_some_and(|status| {
+                        status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+                    })
+            }
+            Self::RateLimited { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the error is caused by the caller's input rather than
+    /// by Kodik or the network.
+    #[must_use]
+    pub const fn is_user_error(&self) -> bool {
+        matches!(
+            self,
+            Self::InvalidUrl { .. }
+                | Self::InvalidArgument { .. }
+                | Self::InvalidHeaderValue(_)
+                | Self::MissingToken
+                | Self::NoSuchTranslation { .. }
+                | Self::NoSuchSeason { .. }
+                | Self::NoSuchEpisode { .. }
+        )
+    }
+}
+
+/// Formats an optional reason as a `: reason` suffix.
+struct Reason<'a>(Option<&'a str>);
+
+impl fmt::Display for Reason<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0
+            .map_or(Ok(()), |reason| write!(f, ": {}", Redacted(reason)))
+    }
+}
+
+/// Formats the translation asked for in [`Error::NoSuchTranslation`].
+struct Translation<'a>(Option<&'a str>, Option<&'a str>);
+
+impl fmt::Display for Translation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.0, self.1) {
+            (None, None) => f.write_str("no video sources found"),
+            (Some(title), None) => write!(f, "no video source with title '{title}'"),
+            (None, Some(kind)) => write!(f, "no video source of type '{kind}'"),
+            (Some(title), Some(kind)) => {
+                write!(
+                    f,
+                    "no video source with title '{title}' or of type '{kind}'"
+                )
+            }
+        }
+    }
+}
+
+/// Formats the season asked for in [`Error::NoSuchSeason`].
+struct Season<'a>(Option<u32>, &'a [u32]);
+
+impl fmt::Display for Season<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(season) = self.0 else {
+            return f.write_str("no season found");
+        };
+        write!(f, "no season {season}, available: ")?;
+        if self.1.is_empty() {
+            return f.write_str("none");
+        }
+        for (idx, available) in self.1.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{available}")?;
+        }
+        Ok(())
+    }
+}
@@ -2,10 +2,20 @@
 #[allow(clippy::unwrap_used, clippy::expect_used, clippy::indexing_slicing)]
 mod tests;
 
+#[cfg(feature = "cookies")]
+pub mod cookies;
+#[cfg(feature = "dns")]
+pub mod dns;
 pub mod error;
+pub mod http;
+pub mod limit;
+pub mod profile;
 pub mod re;
+pub mod redact;
 pub mod ua;
 
-pub use error::Error;
+pub use error::{BoxError, Error, Stage};
+pub use limit::RATE_LIMITER;
+pub use profile::{BrowserProfile, session_headers, session_profile, set_session_profile};
 pub use re::extract_domain;
 pub use ua::random_user_agent;
@@ -0,0 +1,366 @@
+//! Per-host rate limiting shared by every request of the Kodik crates.
+//!
+//! Each host gets token buckets sized by its [`HostGroup`]'s [`RateLimit`].
+//! A `429 Too Many Requests` pauses the host for as long as its
+//! `Retry-After` header asks, up to [`MAX_RETRY_AFTER`], so the rest of a
+//! batch waits instead of getting the IP banned.
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use reqwest::{StatusCode, Url, header::HeaderMap};
+
+use crate::{
+    Error, Stage,
+    http::{HostGroup, error_reason, retry_after},
+    redact::Redacted,
+};
+
+/// How many times a request rejected with `429` is sent again.
+pub const RATE_LIMIT_RETRIES: u32 = 2;
+
+/// Longest `Retry-After` a request is retried after. Longer pauses are
+/// returned to the caller as `Error::RateLimited`.
+pub const MAX_RETRY_AFTER: Duration = Duration::from_mins(1);
+
+/// Pause after a `429` without a `Retry-After` header.
+const DEFAULT_BACK_OFF: Duration = Duration::from_secs(5);
+
+/// Shared limiter used by `kodik_parser` and `kodik_shiki`.
+pub static RATE_LIMITER: LazyLock<RateLimiter> = LazyLock::new(RateLimiter::default);
+
+/// Request budget of a single host. `None` leaves that window unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RateLimit {
+    /// Requests allowed per second.
+    pub per_second: Option<u32>,
+    /// Requests allowed per minute.
+    pub per_minute: Option<u32>,
+}
+
+impl RateLimit {
+    /// No limit at all.
+    pub const UNLIMITED: Self = Self {
+        per_second: None,
+        per_minute: None,
+    };
+
+    fn windows(self) -> impl Iterator<Item = (u32, Duration)> {
+        [
+            self.per_second.map(|n| (n, Duration::from_secs(1))),
+            self.per_minute.map(|n| (n, Duration::from_mins(1))),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|&(n, _)| n > 0)
+    }
+}
+
+/// [`RateLimit`]s by [`HostGroup`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimits {
+    /// Limit for [`HostGroup::KodikApi`].
+    pub kodik_api: RateLimit,
+    /// Limit for [`HostGroup::Shikimori`], by default its published limits.
+    pub shikimori: RateLimit,
+    /// Limit for [`HostGroup::Player`].
+    pub player: RateLimit,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            kodik_api: RateLimit {
+                per_second: Some(5),
+                per_minute: None,
+            },
+            shikimori: RateLimit {
+                per_second: Some(5),
+                per_minute: Some(90),
+            },
+            player: RateLimit {
+                per_second: Some(10),
+                per_minute: None,
+            },
+        }
+    }
+}
+
+impl RateLimits {
+    /// Returns the limit for `group`.
+    #[must_use]
+    pub const fn get(&self, group: HostGroup) -> RateLimit {
+        match group {
+            HostGroup::KodikApi => self.kodik_api,
+            HostGroup::Shikimori => self.shikimori,
+            HostGroup::Player => self.player,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    // Tokens refilled per second. Negative token counts are reservations
+    // of callers already waiting.
+    rate: f64,
+    tokens: f64,
+}
+
+#[derive(Debug)]
+struct Host {
+    buckets: Vec<Bucket>,
+    updated: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl Host {
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        let buckets = limit
+            .windows()
+            .map(|(n, window)| Bucket {
+                capacity: f64::from(n),
+                rate: f64::from(n) / window.as_secs_f64(),
+                tokens: f64::from(n),
+            })
+            .collect();
+        Self {
+            buckets,
+            updated: now,
+            paused_until: None,
+        }
+    }
+
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.updated = now;
+
+        let mut wait = self
+            .paused_until
+            .map_or(Duration::ZERO, |until| until.saturating_duration_since(now));
+        for bucket in &mut self.buckets {
+            bucket.tokens = elapsed
+                .mul_add(bucket.rate, bucket.tokens)
+                .min(bucket.capacity)
+                - 1.0;
+            if bucket.tokens < 0.0 {
+                wait = wait.max(Duration::from_secs_f64(-bucket.tokens / bucket.rate));
+            }
+        }
+        wait
+    }
+}
+
+/// Token-bucket rate limiter keyed by host.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    limits: RateLimits,
+    hosts: HashMap<String, Host>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter with the given limits.
+    #[must_use]
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            state: Mutex::new(State {
+                limits,
+                hosts: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Returns the current limits.
+    #[must_use]
+    pub fn limits(&self) -> RateLimits {
+        self.lock().limits
+    }
+
+    /// Replaces the limits. Hosts start over with full buckets, pauses
+    /// requested by servers are kept.
+    pub fn set_limits(&self, limits: RateLimits) {
+        let mut state = self.lock();
+        state.limits = limits;
+        let now = Instant::now();
+        for (host, entry) in &mut state.hosts {
+            let paused_until = entry.paused_until;
+            *entry = Host::new(limits.get(HostGroup::of(host)), now);
+            entry.paused_until = paused_until;
+        }
+    }
+
+    /// Takes a slot for a request to `url` and returns how long to wait
+    /// before sending it.
+    pub fn reserve(&self, url: &str) -> Duration {
+        let Some(host) = host(url) else {
+            return Duration::ZERO;
+        };
+        let now = Instant::now();
+        let mut state = self.lock();
+        let limit = state.limits.get(HostGroup::of(&host));
+        state
+            .hosts
+            .entry(host)
+            .or_insert_with(|| Host::new(limit, now))
+            .reserve(now)
+    }
+
+    /// Waits until a request to `url` may be sent.
+    pub async fn acquire(&self, url: &str) {
+        let wait = self.reserve(url);
+        if !wait.is_zero() {
+            log::debug!("Rate limit: waiting {wait:?} before {}", Redacted(url));
+            futures_timer::Delay::new(wait).await;
+        }
+    }
+
+    /// Blocks the thread until a request to `url` may be sent.
+    pub fn acquire_blocking(&self, url: &str) {
+        let wait = self.reserve(url);
+        if !wait.is_zero() {
+            log::debug!("Rate limit: waiting {wait:?} before {}", Redacted(url));
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// Sends the request made by `build` once the limiter allows it, and
+    /// sends it again after a `429 Too Many Requests` as [`Self::back_off`]
+    /// decides. `on_retry` is called before each retry with the number of the
+    /// upcoming attempt, starting at 2, and the pause before it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Request` if the request cannot be sent, or
+    /// `Error::RateLimited` if it is still answered with `429` after the
+    /// retries. Other error statuses are left to the caller.
+    pub async fn send(
+        &self,
+        stage: Stage,
+        url: &str,
+        build: impl Fn() -> reqwest::RequestBuilder + Send,
+        mut on_retry: impl FnMut(u32, Duration) + Send,
+    ) -> Result<reqwest::Response, Error> {
+        let mut retry = 0;
+        loop {
+            self.acquire(url).await;
+            let response = build().send().await.map_err(Error::request(stage, url))?;
+            let status = response.status();
+            match self.back_off(url, status, response.headers(), retry) {
+                Some(pause) => {
+                    retry += 1;
+                    on_retry(retry + 1, pause);
+                }
+                None if status == StatusCode::TOO_MANY_REQUESTS => {
+                    let retry_after = retry_after(response.headers());
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(rate_limited(stage, url, retry_after, &body));
+                }
+                None => return Ok(response),
+            }
+        }
+    }
+
+    /// Blocking version of [`Self::send`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Request` if the request cannot be sent, or
+    /// `Error::RateLimited` if it is still answered with `429` after the
+    /// retries.
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking(
+        &self,
+        stage: Stage,
+        url: &str,
+        build: impl Fn() -> reqwest::blocking::RequestBuilder,
+        mut on_retry: impl FnMut(u32, Duration),
+    ) -> Result<reqwest::blocking::Response, Error> {
+        let mut retry = 0;
+        loop {
+            self.acquire_blocking(url);
+            let response = build().send().map_err(Error::request(stage, url))?;
+            let status = response.status();
+            match self.back_off(url, status, response.headers(), retry) {
+                Some(pause) => {
+                    retry += 1;
+                    on_retry(retry + 1, pause);
+                }
+                None if status == StatusCode::TOO_MANY_REQUESTS => {
+                    let retry_after = retry_after(response.headers());
+                    let body = response.text().unwrap_or_default();
+                    return Err(rate_limited(stage, url, retry_after, &body));
+                }
+                None => return Ok(response),
+            }
+        }
+    }
+
+    /// Checks a response for `429 Too Many Requests` and pauses the host of
+    /// `url` for as long as the server asked, but at most
+    /// [`MAX_RETRY_AFTER`].
+    ///
+    /// Returns the pause if the request should be sent again after it: the
+    /// response is a `429`, fewer than [`RATE_LIMIT_RETRIES`] retries were
+    /// made and the requested pause is at most [`MAX_RETRY_AFTER`].
+    pub fn back_off(
+        &self,
+        url: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        retry: u32,
+    ) -> Option<Duration> {
+        if status != StatusCode::TOO_MANY_REQUESTS {
+            return None;
+        }
+        let host = host(url)?;
+
+        let pause = retry_after(headers).unwrap_or(DEFAULT_BACK_OFF);
+        log::warn!("Rate limited by {host}, pausing it for {pause:?}");
+
+        self.pause(host, pause);
+
+        (retry < RATE_LIMIT_RETRIES && pause <= MAX_RETRY_AFTER).then_some(pause)
+    }
+
+    fn pause(&self, host: String, pause: Duration) {
+        let now = Instant::now();
+        // A longer pause fails the request instead, later ones need not wait
+        // for it.
+        let until = now + pause.min(MAX_RETRY_AFTER);
+        let mut state = self.lock();
+        let limit = state.limits.get(HostGroup::of(&host));
+        let entry = state
+            .hosts
+            .entry(host)
+            .or_insert_with(|| Host::new(limit, now));
+        entry.paused_until = Some(entry.paused_until.map_or(until, |known| known.max(until)));
+        drop(state);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn rate_limited(stage: Stage, url: &str, retry_after: Option<Duration>, body: &str) -> Error {
+    Error::RateLimited {
+        stage,
+        url: url.to_owned(),
+        retry_after,
+        reason: error_reason(body),
+    }
+}
+
+fn host(url: &str) -> Option<String> {
+    Url::parse(url)
+        .ok()?
+        .host_str()
+        .map(str::to_ascii_lowercase)
+}
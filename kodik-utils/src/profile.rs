@@ -0,0 +1,179 @@
+//! Browser profile shared by every request of a session.
+//!
+//! Real browsers send the same `User-Agent` and client hints with every
+//! request. Picking a new agent per request, as [`crate::random_user_agent`]
+//! does, makes the page fetch and the API call of a single parse look like
+//! they come from different browsers.
+
+use std::sync::{Arc, LazyLock, PoisonError, RwLock};
+
+use fastrand::Rng;
+use reqwest::header::{ACCEPT_LANGUAGE, HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+
+use crate::Error;
+
+const SEC_CH_UA: HeaderName = HeaderName::from_static("sec-ch-ua");
+const SEC_CH_UA_MOBILE: HeaderName = HeaderName::from_static("sec-ch-ua-mobile");
+const SEC_CH_UA_PLATFORM: HeaderName = HeaderName::from_static("sec-ch-ua-platform");
+
+/// Desktop browsers a profile is picked from: user agent, client hint brands
+/// and platform. Browsers that do not send client hints have no brands.
+const BROWSERS: &[(&str, Option<&str>, &str)] = &[
+    (
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/141.0.0.0 Safari/537.36",
+        Some(r#""Google Chrome";v="141", "Not?A_Brand";v="8", "Chromium";v="141""#),
+        "Windows",
+    ),
+    (
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/141.0.0.0 Safari/537.36",
+        Some(r#""Google Chrome";v="141", "Not?A_Brand";v="8", "Chromium";v="141""#),
+        "macOS",
+    ),
+    (
+        "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/141.0.0.0 Safari/537.36",
+        Some(r#""Google Chrome";v="141", "Not?A_Brand";v="8", "Chromium";v="141""#),
+        "Linux",
+    ),
+    (
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/141.0.0.0 Safari/537.36 Edg/141.0.0.0",
+        Some(r#""Microsoft Edge";v="141", "Not?A_Brand";v="8", "Chromium";v="141""#),
+        "Windows",
+    ),
+    (
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/141.0.0.0 YaBrowser/25.10.0.0 Safari/537.36",
+        Some(r#""Chromium";v="141", "Not?A_Brand";v="8", "YaBrowser";v="25.10""#),
+        "Windows",
+    ),
+    (
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:144.0) Gecko/20100101 Firefox/144.0",
+        None,
+        "Windows",
+    ),
+    (
+        "Mozilla/5.0 (X11; Linux x86_64; rv:144.0) Gecko/20100101 Firefox/144.0",
+        None,
+        "Linux",
+    ),
+    (
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/26.0 Safari/605.1.15",
+        None,
+        "macOS",
+    ),
+];
+
+const DEFAULT_BROWSER: (&str, Option<&str>, &str) = (
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:144.0) Gecko/20100101 Firefox/144.0",
+    None,
+    "Windows",
+);
+
+const DEFAULT_ACCEPT_LANGUAGE: &str = "ru-RU,ru;q=0.9";
+
+const ACCEPT_LANGUAGES: &[&str] = &[
+    "ru-RU,ru;q=0.9,en-US;q=0.8,en;q=0.7",
+    "ru,en;q=0.9",
+    "ru-RU,ru;q=0.8,en-US;q=0.5,en;q=0.3",
+    "ru-RU,ru;q=0.9",
+];
+
+static SESSION_PROFILE: LazyLock<RwLock<Arc<BrowserProfile>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(BrowserProfile::random())));
+
+/// Identity of a desktop browser: user agent and the headers that go with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrowserProfile {
+    /// `User-Agent` header.
+    pub user_agent: String,
+    /// `Accept-Language` header.
+    pub accept_language: String,
+    /// `sec-ch-ua` header, `None` for browsers that do not send client hints.
+    pub sec_ch_ua: Option<String>,
+    /// Platform for the `sec-ch-ua-platform` header, e.g. `Windows`.
+    pub platform: String,
+}
+
+impl BrowserProfile {
+    /// Picks a desktop browser profile at random.
+    #[must_use]
+    pub fn random() -> Self {
+        Self::from_rng(&mut Rng::new())
+    }
+
+    /// Picks a desktop browser profile determined by `seed`. The same seed
+    /// always gives the same profile.
+    #[must_use]
+    pub fn from_seed(seed: u64) -> Self {
+        Self::from_rng(&mut Rng::with_seed(seed))
+    }
+
+    fn from_rng(rng: &mut Rng) -> Self {
+        let (user_agent, sec_ch_ua, platform) =
+            rng.choice(BROWSERS).copied().unwrap_or(DEFAULT_BROWSER);
+        let accept_language = rng
+            .choice(ACCEPT_LANGUAGES)
+            .copied()
+            .unwrap_or(DEFAULT_ACCEPT_LANGUAGE);
+
+        Self {
+            user_agent: user_agent.to_owned(),
+            accept_language: accept_language.to_owned(),
+            sec_ch_ua: sec_ch_ua.map(ToOwned::to_owned),
+            platform: platform.to_owned(),
+        }
+    }
+
+    /// Returns the headers identifying the browser.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidHeaderValue` if a field is not a valid header value.
+    pub fn headers(&self) -> Result<HeaderMap, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_str(&self.user_agent)?);
+        headers.insert(
+            ACCEPT_LANGUAGE,
+            HeaderValue::from_str(&self.accept_language)?,
+        );
+
+        if let Some(sec_ch_ua) = &self.sec_ch_ua {
+            headers.insert(SEC_CH_UA, HeaderValue::from_str(sec_ch_ua)?);
+            headers.insert(SEC_CH_UA_MOBILE, HeaderValue::from_static("?0"));
+            headers.insert(
+                SEC_CH_UA_PLATFORM,
+                HeaderValue::from_str(&format!("\"{}\"", self.platform))?,
+            );
+        }
+
+        Ok(headers)
+    }
+}
+
+/// Returns the profile used by every request of the current process.
+///
+/// Picked at random on first use unless set with [`set_session_profile`].
+#[must_use]
+pub fn session_profile() -> Arc<BrowserProfile> {
+    Arc::clone(
+        &SESSION_PROFILE
+            .read()
+            .unwrap_or_else(PoisonError::into_inner),
+    )
+}
+
+/// Replaces the profile used by every following request.
+pub fn set_session_profile(profile: BrowserProfile) {
+    log::debug!("Using browser profile: {}", profile.user_agent);
+    *SESSION_PROFILE
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Arc::new(profile);
+}
+
+/// Returns the headers of the session profile.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidHeaderValue` if the session profile was set to one
+/// with an invalid field.
+pub fn session_headers() -> Result<HeaderMap, Error> {
+    session_profile().headers()
+}
@@ -6,7 +6,7 @@ use crate::Error;
 ///
 /// # Errors
 ///
-/// Returns `KodikError::Regex` if no valid domain is found in the URL.
+/// Returns `Error::InvalidUrl` if no valid domain is found in the URL.
 pub fn extract_domain(url: &str) -> Result<&str, Error> {
     let domain_re = lazy_regex::regex!(
         r"(?:[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\.)+[a-z0-9][a-z0-9-]{0,61}[a-z0-9]"
@@ -16,9 +16,10 @@ pub fn extract_domain(url: &str) -> Result<&str, Error> {
 
     let domain = domain_re
         .find(url)
-        .ok_or(Error::RegexMatch(format!(
-            "no valid domain found in '{url}'"
-        )))?
+        .ok_or_else(|| Error::InvalidUrl {
+            url: url.to_owned(),
+            reason: "no valid domain found".to_owned(),
+        })?
         .as_str();
 
     log::trace!("Extracted domain: {domain}");
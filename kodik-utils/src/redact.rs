@@ -0,0 +1,103 @@
+//! Masking of secrets in log lines and error messages.
+//!
+//! Verbose logs end up pasted into issues, so the Kodik API token, cookies,
+//! player signatures and signed CDN links are replaced with [`MASK`] before
+//! they are printed. Redaction is on by default, turn it off with
+//! [`set_enabled`] for local debugging.
+use std::{
+    borrow::Cow,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use lazy_regex::regex;
+
+/// Replacement for masked values.
+pub const MASK: &str = "[redacted]";
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Returns `true` if secrets are masked, the default.
+#[must_use]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Turns masking on or off for the whole process.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Masks the secrets in `text`, or returns it unchanged if redaction is off.
+#[must_use]
+pub fn redact(text: &str) -> Cow<'_, str> {
+    if !is_enabled() {
+        return Cow::Borrowed(text);
+    }
+
+    let rules = [
+        // Query parameters holding tokens or signatures, e.g. `?token=...`.
+        (
+            regex!(
+                r"(?i)([?&](?:token|api_key|apikey|access_token|key|signature|sig|sign|[a-z]+_sign|expires|md5|s|e)=)[^&#\s'\x22]+"
+            ),
+            format!("${{1}}{MASK}"),
+        ),
+        // Signature segment of a CDN link, `/<hex signature>:<YYYYMMDDHH>/`.
+        (
+            regex!(r"/[0-9a-f]{16,}:(\d{10})/"),
+            format!("/{MASK}:${{1}}/"),
+        ),
+        // Cookie, `Set-Cookie` and `Authorization` headers, as `Name: value`
+        // or `"name": "value"`.
+        (
+            regex!(r#"(?i)\b((?:set-)?cookie|authorization)(['"]?\s*[:=]\s*['"]?)[^'"\r\n]+"#),
+            format!("${{1}}${{2}}{MASK}"),
+        ),
+        // Session cookies wherever they appear.
+        (
+            regex!(r"(?i)\b(_kawai_session|_kodik_session|remember_user_token)=[^;\s'\x22&]+"),
+            format!("${{1}}={MASK}"),
+        ),
+        // JSON or JavaScript fields holding tokens or signatures, e.g. `"d_sign":"..."`.
+        (
+            regex!(
+                r#"(?i)(['"](?:token|access_token|[a-z]*_sign|signature)['"]\s*:\s*['"])[^'"]*"#
+            ),
+            format!("${{1}}{MASK}"),
+        ),
+    ];
+
+    let mut text = Cow::Borrowed(text);
+    for (re, replacement) in rules {
+        if let Cow::Owned(replaced) = re.replace_all(&text, replacement.as_str()) {
+            text = Cow::Owned(replaced);
+        }
+    }
+    text
+}
+
+/// Displays the wrapped value with its secrets masked.
+///
+/// # Example
+/// ```
+/// use kodik_utils::redact::Redacted;
+///
+/// let url = "https://kodik-api.com/search?token=secret&shikimori_id=20";
+/// assert_eq!(
+///     "https://kodik-api.com/search?token=[redacted]&shikimori_id=20",
+///     Redacted(url).to_string()
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Redacted<T>(pub T);
+
+impl<T: fmt::Display> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if is_enabled() {
+            f.write_str(&redact(&self.0.to_string()))
+        } else {
+            self.0.fmt(f)
+        }
+    }
+}
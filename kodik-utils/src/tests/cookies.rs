@@ -0,0 +1,69 @@
+use std::{env, fs, process};
+
+use reqwest::Url;
+
+use crate::{Error, cookies::CookieJar, http::ClientBuilder};
+
+fn shikimori() -> Url {
+    Url::parse("https://shikimori.one/api/animes/1").unwrap()
+}
+
+#[test]
+fn adding_header_cookies() {
+    let jar = CookieJar::new();
+    assert!(jar.is_empty());
+    assert_eq!(None, jar.header(&shikimori()));
+
+    jar.add_header("_kawai_session=abc; theme=dark;; =broken", &shikimori());
+
+    assert!(!jar.is_empty());
+    assert_eq!(
+        Some("abc".to_owned()),
+        jar.get(&shikimori(), "_kawai_session")
+    );
+    let header = jar.header(&shikimori()).unwrap();
+    assert!(header.contains("_kawai_session=abc"));
+    assert!(header.contains("theme=dark"));
+    assert_eq!(
+        None,
+        jar.header(&Url::parse("https://kodik.info/").unwrap())
+    );
+}
+
+#[test]
+fn saving_and_loading() {
+    let dir = env::temp_dir().join(format!("kodik-cookies-{}", process::id()));
+    let path = dir.join("cookies.json");
+
+    assert!(CookieJar::load(&path).unwrap().is_empty());
+
+    let jar = CookieJar::new();
+    jar.add_header("_kawai_session=abc", &shikimori());
+    jar.save(&path).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(0o600, mode & 0o777);
+    }
+
+    let loaded = CookieJar::load(&path).unwrap();
+    assert_eq!(
+        Some("abc".to_owned()),
+        loaded.get(&shikimori(), "_kawai_session")
+    );
+
+    fs::write(&path, "not json").unwrap();
+    assert!(matches!(
+        CookieJar::load(&path),
+        Err(Error::CookieJar { .. })
+    ));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn client_with_cookie_jar() {
+    let jar = CookieJar::new();
+    assert!(ClientBuilder::new().cookie_jar(&jar).build().is_ok());
+}
@@ -0,0 +1,30 @@
+use std::net::SocketAddr;
+
+use crate::{
+    dns::{Nameservers, parse_nameserver},
+    http::ClientBuilder,
+};
+
+#[test]
+fn parsing_nameservers() {
+    assert_eq!(
+        SocketAddr::from(([1, 1, 1, 1], 53)),
+        parse_nameserver("1.1.1.1").unwrap()
+    );
+    assert_eq!(
+        SocketAddr::from(([9, 9, 9, 9], 5353)),
+        parse_nameserver(" 9.9.9.9:5353 ").unwrap()
+    );
+    assert_eq!(
+        "[2606:4700:4700::1111]:53".parse::<SocketAddr>().unwrap(),
+        parse_nameserver("2606:4700:4700::1111").unwrap()
+    );
+    assert!(parse_nameserver("dns.google").unwrap_err().is_user_error());
+}
+
+#[test]
+fn client_with_nameservers() {
+    let servers = vec![SocketAddr::from(([1, 1, 1, 1], 53))];
+    assert_eq!(servers, Nameservers::new(servers.clone()).servers());
+    assert!(ClientBuilder::new().nameservers(servers).build().is_ok());
+}
@@ -0,0 +1,92 @@
+use std::error::Error as _;
+
+use crate::{Error, Stage, extract_domain};
+
+#[test]
+fn invalid_url_is_user_error() {
+    let err = extract_domain("not a url").unwrap_err();
+
+    assert_eq!(Some(Stage::Url), err.stage());
+    assert_eq!(Some("not a url"), err.url());
+    assert!(err.is_user_error());
+    assert!(!err.is_retryable());
+}
+
+#[test]
+fn invalid_argument_is_user_error() {
+    let err = Error::invalid_argument("x", "expected a number");
+
+    assert_eq!(None, err.stage());
+    assert_eq!(None, err.url());
+    assert!(err.is_user_error());
+    assert_eq!("invalid value 'x': expected a number", err.to_string());
+}
+
+#[test]
+fn unexpected_response_carries_stage_and_url() {
+    let url = "https://kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p";
+    let err = Error::unexpected(Stage::VideoInfo, url, "videoInfo.hash not found");
+
+    assert_eq!(Some(Stage::VideoInfo), err.stage());
+    assert_eq!(Some(url), err.url());
+    assert!(!err.is_user_error());
+    assert!(!err.is_retryable());
+    assert_eq!(
+        format!("video info: unexpected response from '{url}': videoInfo.hash not found"),
+        err.to_string()
+    );
+}
+
+#[test]
+fn decode_error_keeps_source() {
+    let url = "https://kodikplayer.com/assets/js/app.player_single.js";
+    let source = String::from_utf8(vec![0xff]).unwrap_err();
+    let err = Error::decode(Stage::Endpoint, url)(source);
+
+    assert_eq!(Some(Stage::Endpoint), err.stage());
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn missing_translation_season_or_episode_is_user_error() {
+    let translation = Error::NoSuchTranslation {
+        title: Some("AniDub".to_owned()),
+        kind: Some("voice".to_owned()),
+    };
+    let season = Error::NoSuchSeason {
+        season: Some(3),
+        available: vec![1, 2],
+    };
+    let episode = Error::NoSuchEpisode {
+        episodes: "7".to_owned(),
+        season: "latest".to_owned(),
+    };
+
+    for err in [&translation, &season, &episode] {
+        assert!(err.is_user_error());
+        assert_eq!(None, err.stage());
+    }
+    assert_eq!(
+        "no video source with title 'AniDub' or of type 'voice'",
+        translation.to_string()
+    );
+    assert_eq!("no season 3, available: 1, 2", season.to_string());
+    assert_eq!("no episode 7 in season latest", episode.to_string());
+    assert_eq!(
+        "no video sources found",
+        Error::NoSuchTranslation {
+            title: None,
+            kind: None
+        }
+        .to_string()
+    );
+}
+
+#[test]
+fn broken_regex_is_not_user_error() {
+    let pattern = String::from("(");
+    let err = Error::from(lazy_regex::regex::Regex::new(&pattern).unwrap_err());
+
+    assert!(!err.is_user_error());
+    assert!(!err.is_retryable());
+}
@@ -0,0 +1,245 @@
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+    time::Duration,
+};
+
+use reqwest::{
+    Client, StatusCode,
+    header::{HeaderMap, HeaderValue, RETRY_AFTER},
+};
+
+use crate::{
+    Error, Stage,
+    http::HostGroup,
+    limit::{MAX_RETRY_AFTER, RATE_LIMIT_RETRIES, RateLimit, RateLimiter, RateLimits},
+};
+
+const SHIKIMORI: &str = "https://shikimori.one/api/animes/1";
+
+fn limits(limit: RateLimit) -> RateLimits {
+    RateLimits {
+        kodik_api: limit,
+        shikimori: limit,
+        player: limit,
+    }
+}
+
+#[test]
+fn default_limits() {
+    let limits = RateLimits::default();
+    assert_eq!(Some(5), limits.get(HostGroup::Shikimori).per_second);
+    assert_eq!(Some(90), limits.get(HostGroup::Shikimori).per_minute);
+    assert!(limits.get(HostGroup::KodikApi).per_second.is_some());
+    assert!(limits.get(HostGroup::Player).per_second.is_some());
+}
+
+#[test]
+fn burst_then_wait() {
+    let limiter = RateLimiter::new(limits(RateLimit {
+        per_second: Some(2),
+        per_minute: None,
+    }));
+
+    assert_eq!(Duration::ZERO, limiter.reserve(SHIKIMORI));
+    assert_eq!(Duration::ZERO, limiter.reserve(SHIKIMORI));
+
+    let third = limiter.reserve(SHIKIMORI);
+    let fourth = limiter.reserve(SHIKIMORI);
+    assert!(third > Duration::from_millis(400) && third <= Duration::from_millis(500));
+    assert!(fourth > third && fourth <= Duration::from_secs(1));
+}
+
+#[test]
+fn hosts_have_separate_buckets() {
+    let limiter = RateLimiter::new(limits(RateLimit {
+        per_second: Some(1),
+        per_minute: None,
+    }));
+
+    assert_eq!(Duration::ZERO, limiter.reserve(SHIKIMORI));
+    assert_eq!(
+        Duration::ZERO,
+        limiter.reserve("https://kodik.info/seria/1")
+    );
+    assert!(limiter.reserve("https://SHIKIMORI.one/other") > Duration::ZERO);
+}
+
+#[test]
+fn per_minute_window() {
+    let limiter = RateLimiter::new(limits(RateLimit {
+        per_second: None,
+        per_minute: Some(1),
+    }));
+
+    assert_eq!(Duration::ZERO, limiter.reserve(SHIKIMORI));
+    assert!(limiter.reserve(SHIKIMORI) > Duration::from_secs(59));
+}
+
+#[test]
+fn unlimited() {
+    let limiter = RateLimiter::new(limits(RateLimit::UNLIMITED));
+    for _ in 0..100 {
+        assert_eq!(Duration::ZERO, limiter.reserve(SHIKIMORI));
+    }
+    assert_eq!(Duration::ZERO, limiter.reserve("not a url"));
+}
+
+#[test]
+fn too_many_requests_pauses_host() {
+    let limiter = RateLimiter::new(limits(RateLimit::UNLIMITED));
+    let mut headers = HeaderMap::new();
+
+    assert_eq!(
+        None,
+        limiter.back_off(SHIKIMORI, StatusCode::OK, &headers, 0)
+    );
+    assert_eq!(Duration::ZERO, limiter.reserve(SHIKIMORI));
+
+    headers.insert(RETRY_AFTER, HeaderValue::from_static("3"));
+    assert_eq!(
+        Some(Duration::from_secs(3)),
+        limiter.back_off(SHIKIMORI, StatusCode::TOO_MANY_REQUESTS, &headers, 0)
+    );
+    let wait = limiter.reserve(SHIKIMORI);
+    assert!(wait > Duration::from_secs(2) && wait <= Duration::from_secs(3));
+    assert_eq!(
+        Duration::ZERO,
+        limiter.reserve("https://kodik.info/seria/1")
+    );
+
+    assert_eq!(
+        None,
+        limiter.back_off(
+            SHIKIMORI,
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers,
+            RATE_LIMIT_RETRIES
+        )
+    );
+}
+
+#[test]
+fn long_retry_after_is_capped() {
+    let limiter = RateLimiter::new(limits(RateLimit::UNLIMITED));
+    let mut headers = HeaderMap::new();
+
+    let just_over = (MAX_RETRY_AFTER.as_secs() + 1).to_string();
+    headers.insert(RETRY_AFTER, HeaderValue::from_str(&just_over).unwrap());
+    assert_eq!(
+        None,
+        limiter.back_off(SHIKIMORI, StatusCode::TOO_MANY_REQUESTS, &headers, 0)
+    );
+    assert!(limiter.reserve(SHIKIMORI) <= MAX_RETRY_AFTER);
+
+    headers.insert(
+        RETRY_AFTER,
+        HeaderValue::from_static("18446744073709551615"),
+    );
+    assert_eq!(
+        None,
+        limiter.back_off(SHIKIMORI, StatusCode::TOO_MANY_REQUESTS, &headers, 0)
+    );
+    let wait = limiter.reserve(SHIKIMORI);
+    assert!(wait + Duration::from_secs(1) > MAX_RETRY_AFTER && wait <= MAX_RETRY_AFTER);
+}
+
+#[test]
+fn changing_limits_keeps_pauses() {
+    let limiter = RateLimiter::new(limits(RateLimit {
+        per_second: Some(1),
+        per_minute: None,
+    }));
+    let mut headers = HeaderMap::new();
+    headers.insert(RETRY_AFTER, HeaderValue::from_static("10"));
+    limiter.back_off(SHIKIMORI, StatusCode::TOO_MANY_REQUESTS, &headers, 0);
+
+    limiter.set_limits(limits(RateLimit::UNLIMITED));
+
+    assert_eq!(limits(RateLimit::UNLIMITED), limiter.limits());
+    assert!(limiter.reserve(SHIKIMORI) > Duration::from_secs(9));
+}
+
+#[test]
+fn acquire_waits() {
+    let limiter = RateLimiter::new(limits(RateLimit {
+        per_second: Some(20),
+        per_minute: None,
+    }));
+    let start = std::time::Instant::now();
+    for _ in 0..22 {
+        limiter.acquire_blocking(SHIKIMORI);
+    }
+    assert!(start.elapsed() >= Duration::from_millis(90));
+}
+
+/// Answers one request per entry of `responses` and returns the server URL.
+fn serve(responses: &'static [&'static str]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for response in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    url
+}
+
+const TOO_MANY_REQUESTS: &str = "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 22\r\nConnection: close\r\n\r\n{\"error\": \"slow down\"}";
+
+#[tokio::test]
+async fn send_retries_after_too_many_requests() {
+    let url = serve(&[
+        TOO_MANY_REQUESTS,
+        "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+    ]);
+    let limiter = RateLimiter::new(limits(RateLimit::UNLIMITED));
+    let client = Client::builder().no_proxy().build().unwrap();
+
+    let mut retries = Vec::new();
+    let response = limiter
+        .send(
+            Stage::Api,
+            &url,
+            || client.get(&url),
+            |attempt, pause| retries.push((attempt, pause)),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!("ok", response.text().await.unwrap());
+    assert_eq!(vec![(2, Duration::ZERO)], retries);
+}
+
+#[tokio::test]
+async fn send_gives_up_after_retries() {
+    let url = serve(&[TOO_MANY_REQUESTS; RATE_LIMIT_RETRIES as usize + 1]);
+    let limiter = RateLimiter::new(limits(RateLimit::UNLIMITED));
+    let client = Client::builder().no_proxy().build().unwrap();
+
+    let mut attempts = Vec::new();
+    let error = limiter
+        .send(
+            Stage::Api,
+            &url,
+            || client.get(&url),
+            |attempt, _| attempts.push(attempt),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        error,
+        Error::RateLimited {
+            stage: Stage::Api,
+            retry_after: Some(Duration::ZERO),
+            ..
+        }
+    ));
+    assert_eq!(Some("slow down"), error.reason());
+    assert_eq!(vec![2, 3], attempts);
+}
@@ -1,2 +1,11 @@
+#[cfg(feature = "cookies")]
+mod cookies;
+#[cfg(feature = "dns")]
+mod dns;
+mod error;
+mod http;
+mod limit;
+mod profile;
 mod re;
+mod redact;
 mod ua;
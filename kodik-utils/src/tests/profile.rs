@@ -0,0 +1,68 @@
+use reqwest::header::{ACCEPT_LANGUAGE, USER_AGENT};
+
+use crate::profile::{BrowserProfile, session_profile, set_session_profile};
+
+#[test]
+fn seeded_profile_is_deterministic() {
+    assert_eq!(BrowserProfile::from_seed(42), BrowserProfile::from_seed(42));
+}
+
+#[test]
+fn profiles_are_desktop_browsers() {
+    for seed in 0..64 {
+        let profile = BrowserProfile::from_seed(seed);
+
+        assert!(!profile.user_agent.contains("Mobile"));
+        assert!(
+            ["Windows", "Macintosh", "X11"]
+                .iter()
+                .any(|os| profile.user_agent.contains(os))
+        );
+        if let Some(sec_ch_ua) = &profile.sec_ch_ua {
+            assert!(profile.user_agent.contains("Chrome/141"));
+            assert!(sec_ch_ua.contains(r#""Chromium";v="141""#));
+        }
+    }
+}
+
+#[test]
+fn profile_headers() {
+    let profile = BrowserProfile {
+        user_agent: "Mozilla/5.0 (X11; Linux x86_64) Chrome/141.0.0.0".to_owned(),
+        accept_language: "ru-RU,ru;q=0.9".to_owned(),
+        sec_ch_ua: Some(r#""Chromium";v="141""#.to_owned()),
+        platform: "Linux".to_owned(),
+    };
+
+    let headers = profile.headers().unwrap();
+
+    assert_eq!(profile.user_agent, headers[USER_AGENT]);
+    assert_eq!("ru-RU,ru;q=0.9", headers[ACCEPT_LANGUAGE]);
+    assert_eq!(r#""Chromium";v="141""#, headers["sec-ch-ua"]);
+    assert_eq!("?0", headers["sec-ch-ua-mobile"]);
+    assert_eq!(r#""Linux""#, headers["sec-ch-ua-platform"]);
+
+    let firefox = BrowserProfile {
+        sec_ch_ua: None,
+        ..profile
+    };
+    assert!(!firefox.headers().unwrap().contains_key("sec-ch-ua"));
+}
+
+#[test]
+fn invalid_profile_is_rejected() {
+    let profile = BrowserProfile {
+        user_agent: "line\nbreak".to_owned(),
+        ..BrowserProfile::from_seed(0)
+    };
+
+    assert!(profile.headers().unwrap_err().is_user_error());
+}
+
+#[test]
+fn session_profile_is_kept() {
+    assert_eq!(session_profile(), session_profile());
+
+    set_session_profile(BrowserProfile::from_seed(7));
+    assert_eq!(BrowserProfile::from_seed(7), *session_profile());
+}
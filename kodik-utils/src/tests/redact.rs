@@ -0,0 +1,50 @@
+use crate::redact::{MASK, Redacted, is_enabled, redact, set_enabled};
+
+// One test, since the switch is global and tests run in parallel.
+#[test]
+fn redacting_secrets() {
+    assert!(is_enabled());
+
+    assert_eq!(
+        format!("https://kodik-api.com/search?token={MASK}&shikimori_id=20"),
+        redact("https://kodik-api.com/search?token=0123456789abcdef&shikimori_id=20")
+    );
+    assert_eq!(
+        format!(
+            "https://p56.kodik.info/s/m/Ly9jbG91ZA/{MASK}:2025101912/720.mp4:hls:manifest.m3u8"
+        ),
+        redact(
+            "https://p56.kodik.info/s/m/Ly9jbG91ZA/0a1b2c3d4e5f60718293a4b5c6d7e8f9:2025101912/720.mp4:hls:manifest.m3u8"
+        )
+    );
+    assert_eq!(
+        format!(r#"var urlParams = '{{"d_sign":"{MASK}","pd_sign":"{MASK}","ref":""}}';"#),
+        redact(r#"var urlParams = '{"d_sign":"abc123","pd_sign":"def456","ref":""}';"#)
+    );
+    assert_eq!(
+        format!(r#"{{"cookie": "{MASK}", "accept": "application/json"}}"#),
+        redact(r#"{"cookie": "_kawai_session=abc", "accept": "application/json"}"#)
+    );
+    assert_eq!(
+        format!("Set-Cookie: {MASK}"),
+        redact("Set-Cookie: _kawai_session=abc; path=/; HttpOnly")
+    );
+    assert_eq!(
+        format!("sent _kawai_session={MASK}; theme=dark"),
+        redact("sent _kawai_session=abc; theme=dark")
+    );
+    assert_eq!(
+        "GET to https://kodik.info/seria/1/abcdef/720p...",
+        redact("GET to https://kodik.info/seria/1/abcdef/720p...")
+    );
+    assert_eq!(
+        format!("?token={MASK}"),
+        Redacted(format_args!("?token={}", "secret")).to_string()
+    );
+
+    set_enabled(false);
+    let url = "https://kodik-api.com/search?token=secret";
+    assert_eq!(url, redact(url));
+    assert_eq!(url, Redacted(url).to_string());
+    set_enabled(true);
+}
@@ -1,10 +1,11 @@
 use std::{
+    collections::BTreeMap,
     fs::{self, File, OpenOptions},
     path::PathBuf,
     sync::LazyLock,
 };
 
-use kodik_parser::KODIK_STATE;
+use kodik_parser::{DomainState, KODIK_STATE, SNAPSHOT_VERSION, StateSnapshot};
 use serde::{Deserialize, Serialize};
 
 use crate::config::Config;
@@ -12,10 +13,25 @@ use crate::config::Config;
 pub static CACHE_PATH: LazyLock<Option<PathBuf>> =
     LazyLock::new(|| dirs::cache_dir().map(|cache_dir| cache_dir.join("kodik").join("cache.json")));
 
+pub static COOKIES_PATH: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
+    dirs::cache_dir().map(|cache_dir| cache_dir.join("kodik").join("cookies.json"))
+});
+
+pub static WATCH_PATH: LazyLock<Option<PathBuf>> =
+    LazyLock::new(|| dirs::cache_dir().map(|cache_dir| cache_dir.join("kodik").join("watch.json")));
+
+pub static CATALOG_PATH: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
+    dirs::cache_dir().map(|cache_dir| cache_dir.join("kodik").join("catalog.sqlite"))
+});
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Cache {
     pub shift: Option<u8>,
     pub endpoint: Option<String>,
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    #[serde(default)]
+    pub domains: BTreeMap<String, DomainState>,
     pub cookie: Option<String>,
     #[serde(skip)]
     pub path: PathBuf,
@@ -53,28 +69,51 @@ impl Cache {
     }
 
     pub fn update(&mut self, cookie: Option<&str>) {
-        self.shift = Some(KODIK_STATE.shift());
-        let endpoint = KODIK_STATE.endpoint().to_string();
-        self.endpoint.clone_from(&Some(endpoint));
+        let snapshot = KODIK_STATE.snapshot();
+        self.shift = Some(snapshot.shift);
+        self.endpoint = Some(snapshot.endpoint);
+        self.fingerprint = snapshot.fingerprint;
+        self.domains = snapshot.domains;
         self.cookie = cookie.map(ToOwned::to_owned);
     }
 
     pub fn is_changed(&self, cookie: Option<&str>) -> bool {
-        self.shift != Some(KODIK_STATE.shift())
-            || self.endpoint.as_deref() != Some(KODIK_STATE.endpoint().as_str())
+        self.differs_from(&KODIK_STATE.snapshot(), cookie)
+    }
+
+    /// Compares the cache with `snapshot`, ignoring when each domain was last
+    /// seen: that changes on every parse and alone is not worth a rewrite.
+    pub fn differs_from(&self, snapshot: &StateSnapshot, cookie: Option<&str>) -> bool {
+        let fingerprints = |domains: &BTreeMap<String, DomainState>| {
+            domains
+                .iter()
+                .map(|(domain, state)| (domain.clone(), state.fingerprint.clone()))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        self.shift != Some(snapshot.shift)
+            || self.endpoint.as_deref() != Some(snapshot.endpoint.as_str())
+            || self.fingerprint != snapshot.fingerprint
+            || fingerprints(&self.domains) != fingerprints(&snapshot.domains)
             || self.cookie.as_deref() != cookie
     }
 
-    pub fn apply(&self, config: &mut Config) {
-        if let Some(shift) = self.shift {
-            KODIK_STATE.set_shift(shift);
-        }
-        if let Some(endpoint) = self.endpoint.clone() {
-            KODIK_STATE.set_endpoint(endpoint);
+    /// Returns the cached parser state.
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            version: SNAPSHOT_VERSION,
+            endpoint: self.endpoint.clone().unwrap_or_default(),
+            shift: self.shift.unwrap_or_default(),
+            fingerprint: self.fingerprint.clone(),
+            domains: self.domains.clone(),
+            // The cache does not record when it was written.
+            timestamp: 0,
         }
+    }
 
-        if config.cookie.is_none() && self.cookie.is_some() {
-            config.cookie.clone_from(&self.cookie.clone());
-        }
+    pub fn apply(&self, config: &mut Config) {
+        KODIK_STATE.restore(&self.snapshot());
+
+        config.cached_cookie.clone_from(&self.cookie);
     }
 }
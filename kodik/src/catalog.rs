@@ -0,0 +1,62 @@
+//! `kodik catalog`: the local copy of the Kodik catalog in the cache directory.
+use std::{process::ExitCode, time::Duration};
+
+use kodik_api::{KodikApi, catalog::Catalog};
+use kodik_parser::reqwest::Client;
+use kodik_utils::Error;
+
+use crate::{cache::CATALOG_PATH, report};
+
+/// How long after a sync the catalog is trusted over the Kodik API for
+/// new episodes.
+pub const CATALOG_TTL: Duration = Duration::from_hours(24);
+
+/// Opens the catalog if `kodik catalog sync` created one.
+pub fn open() -> Option<Catalog> {
+    let path = CATALOG_PATH.as_ref().filter(|path| path.exists())?;
+    Catalog::open(path)
+        .inspect_err(|e| log::warn!("{}", report(e)))
+        .ok()
+}
+
+/// Returns `true` if `catalog` was synced less than [`CATALOG_TTL`] ago.
+pub fn is_fresh(catalog: &Catalog) -> bool {
+    match catalog.synced_at() {
+        Ok(synced_at) => synced_at
+            .and_then(|synced_at| synced_at.elapsed().ok())
+            .is_some_and(|elapsed| elapsed < CATALOG_TTL),
+        Err(e) => {
+            log::warn!("{}", report(&e));
+            false
+        }
+    }
+}
+
+/// `kodik catalog sync`: creates or updates the catalog.
+pub async fn sync(client: &Client, kodik_token: Option<&str>) -> ExitCode {
+    match try_sync(client, kodik_token).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            log::error!("{}", report(&e));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn try_sync(client: &Client, kodik_token: Option<&str>) -> Result<(), Error> {
+    let token = kodik_shiki::token::resolve(kodik_token)?;
+    let path = CATALOG_PATH
+        .as_ref()
+        .ok_or_else(|| Error::catalog("no cache directory for the catalog"))?;
+    let catalog = Catalog::open(path)?;
+
+    let stats = catalog.sync(&KodikApi::new(client.clone(), token)).await?;
+    log::info!(
+        "Catalog updated: {} material(s) from {} page(s), {} in total, in {}",
+        stats.materials,
+        stats.pages,
+        catalog.len()?,
+        path.display()
+    );
+    Ok(())
+}
@@ -1,6 +1,18 @@
-use std::{collections::HashMap, fmt::Write, str::FromStr, sync::LazyLock};
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::LazyLock,
+    time::Duration,
+};
 
-use kodik_shiki::TranslationType;
+use kodik_api::{MaterialType, Track};
+use kodik_shiki::{EpisodeSelector, ExternalId, SeasonSelector, TranslationType};
+use kodik_utils::{
+    dns::parse_nameserver,
+    http::{ProxyConfig, parse_resolve},
+};
 use log::LevelFilter;
 
 use crate::logging::{
@@ -16,6 +28,8 @@ pub enum ArgAction {
     Count,
     /// Appends each token to a list (positional arguments).
     Append,
+    /// Appends the next token to a list each time the option is given.
+    Push,
 }
 
 pub struct Arg {
@@ -220,7 +234,7 @@ impl Command {
                 ArgAction::Count => {
                     values.insert(arg.id, MatchValue::Count(0));
                 }
-                ArgAction::Append => {
+                ArgAction::Append | ArgAction::Push => {
                     values.insert(arg.id, MatchValue::Multiple(Vec::new()));
                 }
                 ArgAction::Set => {}
@@ -303,6 +317,18 @@ impl Command {
                 })?;
                 values.insert(arg.id, MatchValue::Single(val));
             }
+            ArgAction::Push => {
+                let val = tokens.next().ok_or_else(|| {
+                    let v = arg.value_name.unwrap_or("VALUE");
+                    format!(
+                        "a value is required for \
+                         '{YELLOW_BOLD}--{long} <{v}>{RESET}' but was not supplied",
+                    )
+                })?;
+                if let Some(MatchValue::Multiple(list)) = values.get_mut(arg.id) {
+                    list.push(val);
+                }
+            }
             ArgAction::Append => {}
         }
 
@@ -346,7 +372,7 @@ impl Command {
                     }
                     ci += 1;
                 }
-                ArgAction::Set => {
+                ArgAction::Set | ArgAction::Push => {
                     let val = if ci + 1 < chars.len() {
                         chars
                             .get(ci + 1..)
@@ -361,7 +387,13 @@ impl Command {
                             )
                         })?
                     };
-                    values.insert(arg.id, MatchValue::Single(val));
+                    if matches!(arg.action, ArgAction::Push) {
+                        if let Some(MatchValue::Multiple(list)) = values.get_mut(arg.id) {
+                            list.push(val);
+                        }
+                    } else {
+                        values.insert(arg.id, MatchValue::Single(val));
+                    }
                     break;
                 }
                 ArgAction::Append => {
@@ -379,7 +411,7 @@ pub static COMMAND: LazyLock<Command> = LazyLock::new(|| {
         .arg(
             Arg::new("url")
                 .value_name("URL")
-                .help("Url(s) to parse")
+                .help("Url(s) or ids (kp:ID, imdb:ID, mdl:ID, shiki:ID, worldart:URL) to parse, search <TITLE>, catalog sync, catalog search <TITLE> or watch <ID>...")
                 .action(ArgAction::Append),
         )
         .arg(
@@ -388,6 +420,14 @@ pub static COMMAND: LazyLock<Command> = LazyLock::new(|| {
                 .long("lazy")
                 .help("Outputs one by one (turns off parallelism)"),
         )
+        .arg(
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .value_name("JOBS")
+                .help("Number of videos parsed at once (default: 8)")
+                .action(ArgAction::Set),
+        )
         .arg(
             Arg::new("player")
                 .short('p')
@@ -417,12 +457,24 @@ pub static COMMAND: LazyLock<Command> = LazyLock::new(|| {
                 .help("Specify video quality [possible values: 360, 480, 720] (default: 720)")
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("Output parsed videos as JSON lines (schema v1), not with --player"),
+        )
         .arg(
             Arg::new("episode")
                 .short('e')
                 .long("episode")
-                .value_name("EPISODE")
-                .help("Specify from which episode start with")
+                .value_name("EPISODES")
+                .help("Episodes to play: 5, 5-, 3-7, 1,4,9-12 or last [default: after those watched on Shikimori]")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("season")
+                .long("season")
+                .value_name("SEASON")
+                .help("Season to play: a number (0 for specials), all or latest [default: latest]")
                 .action(ArgAction::Set),
         )
         .arg(
@@ -439,6 +491,48 @@ pub static COMMAND: LazyLock<Command> = LazyLock::new(|| {
                 .help("Specify translation type [possible values: voice, subtitles]")
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new("material_type")
+                .long("material-type")
+                .value_name("TYPE")
+                .help("With search, only show materials of TYPE, e.g. anime-serial (repeatable)")
+                .action(ArgAction::Push),
+        )
+        .arg(
+            Arg::new("year")
+                .long("year")
+                .value_name("YEAR")
+                .help("With search, only show materials released in YEAR (repeatable)")
+                .action(ArgAction::Push),
+        )
+        .arg(
+            Arg::new("genre")
+                .long("genre")
+                .value_name("GENRE")
+                .help("With search, only show materials of GENRE (repeatable)")
+                .action(ArgAction::Push),
+        )
+        .arg(
+            Arg::new("pick")
+                .long("pick")
+                .value_name("N")
+                .help("With search, play the N-th result without asking")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("interval")
+                .long("interval")
+                .value_name("MINUTES")
+                .help("With watch, keep polling every MINUTES instead of once")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("hook")
+                .long("hook")
+                .value_name("COMMAND")
+                .help("With watch, run COMMAND for every new episode")
+                .action(ArgAction::Set),
+        )
         .arg(
             Arg::new("cookie")
                 .long("cookie")
@@ -446,6 +540,60 @@ pub static COMMAND: LazyLock<Command> = LazyLock::new(|| {
                 .help("Specify cookie to get your user rate")
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new("kodik_token")
+                .long("kodik-token")
+                .value_name("TOKEN")
+                .help("Kodik API token for Shikimori links (default: $KODIK_TOKEN)")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("proxy")
+                .long("proxy")
+                .value_name("URL")
+                .help("Proxy for all requests [http, https, socks5 or socks5h]")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("proxy_kodik_api")
+                .long("proxy-kodik-api")
+                .value_name("URL")
+                .help("Proxy for kodik-api.com (overrides --proxy)")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("proxy_shikimori")
+                .long("proxy-shikimori")
+                .value_name("URL")
+                .help("Proxy for Shikimori (overrides --proxy)")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("proxy_player")
+                .long("proxy-player")
+                .value_name("URL")
+                .help("Proxy for Kodik player domains (overrides --proxy)")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("resolve")
+                .long("resolve")
+                .value_name("HOST:ADDR")
+                .help("Resolve HOST to ADDR instead of asking DNS (repeatable)")
+                .action(ArgAction::Push),
+        )
+        .arg(
+            Arg::new("dns")
+                .long("dns")
+                .value_name("ADDR")
+                .help("Use this DNS server instead of the system one (repeatable)")
+                .action(ArgAction::Push),
+        )
+        .arg(
+            Arg::new("no_redact")
+                .long("no-redact")
+                .help("Show tokens, cookies and signatures in logs (for local debugging)"),
+        )
         .arg(Arg::new("help").short('h').long("help").help("Print help"))
 });
 
@@ -492,17 +640,57 @@ For more information, try '{CYAN_HIGH_INTENSITY_BOLD}--help{RESET}'."
     }
 }
 
+pub const DEFAULT_JOBS: usize = 8;
+
+/// `kodik search <TITLE>...`: find materials by title instead of parsing URLs.
+pub struct Search {
+    pub title: String,
+    pub years: Vec<u16>,
+    pub types: Vec<MaterialType>,
+    pub genres: Vec<String>,
+    /// Searched in the local catalog instead of the API, see
+    /// `kodik catalog search <TITLE>`.
+    pub offline: bool,
+    /// 1-based index of the result to play.
+    pub pick: Option<usize>,
+}
+
+/// `kodik watch <ID>...`: report new episodes of tracked materials.
+pub struct Watch {
+    pub tracks: Vec<Track>,
+    /// Time between polls, a single poll if `None`.
+    pub interval: Option<Duration>,
+    /// Command run for every new episode.
+    pub hook: Option<String>,
+}
+
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config {
     pub urls: Vec<String>,
     pub level_filter: LevelFilter,
     pub lazy: bool,
+    pub jobs: usize,
     pub help: bool,
     pub player: Option<String>,
     pub quality: Quality,
+    pub json: bool,
+    pub no_redact: bool,
     pub translation_title: Option<String>,
     pub translation_type: TranslationTypeArg,
-    pub episode: Option<usize>,
+    pub season: SeasonSelector,
+    pub episode: Option<EpisodeSelector>,
     pub cookie: Option<String>,
+    /// Cookie stored in `cache.json` by older versions, moved into the
+    /// cookie jar the first time it is needed.
+    pub cached_cookie: Option<String>,
+    pub kodik_token: Option<String>,
+    pub proxies: ProxyConfig,
+    pub resolve: Vec<(String, IpAddr)>,
+    pub dns: Vec<SocketAddr>,
+    pub search: Option<Search>,
+    /// `kodik catalog sync`: update the local catalog instead of parsing URLs.
+    pub catalog_sync: bool,
+    pub watch: Option<Watch>,
 }
 
 impl Config {
@@ -529,27 +717,246 @@ impl Config {
             None => TranslationTypeArg(None),
         };
 
-        let episode = m.get_one("episode").map(|src| {
-            usize::from_str(src).map_err(|_| {
-                format!(
-                "invalid value '{YELLOW_BOLD}{src}{RESET}' for '{CYAN_HIGH_INTENSITY_BOLD}-e{RESET}, {CYAN_HIGH_INTENSITY_BOLD}--episode <EPISODE>{RESET}'\n
+        let jobs = match m.get_one("jobs") {
+            Some(src) => usize::from_str(src)
+                .ok()
+                .filter(|jobs| *jobs > 0)
+                .ok_or_else(|| {
+                    format!(
+                "invalid value '{YELLOW_BOLD}{src}{RESET}' for '{CYAN_HIGH_INTENSITY_BOLD}-j{RESET}, {CYAN_HIGH_INTENSITY_BOLD}--jobs <JOBS>{RESET}'\n
 For more information, try '{CYAN_HIGH_INTENSITY_BOLD}--help{RESET}'."
-                )
+                    )
+                })?,
+            None => DEFAULT_JOBS,
+        };
+
+        let episode = m
+            .get_one("episode")
+            .map(|src| {
+                src.parse()
+                    .map_err(|e| invalid("-e, --episode <EPISODES>", &e))
             })
-        })
-        .transpose()?;
+            .transpose()?;
+
+        let resolve = m
+            .get_many("resolve")
+            .iter()
+            .map(|src| parse_resolve(src).map_err(|e| invalid("--resolve <HOST:ADDR>", &e)))
+            .collect::<Result<_, _>>()?;
+
+        let dns = m
+            .get_many("dns")
+            .iter()
+            .map(|src| parse_nameserver(src).map_err(|e| invalid("--dns <ADDR>", &e)))
+            .collect::<Result<_, _>>()?;
+
+        let season = m
+            .get_one("season")
+            .map(|src| src.parse().map_err(|e| invalid("--season <SEASON>", &e)))
+            .transpose()?
+            .unwrap_or_default();
+
+        let player = m.get_one("player").map(str::to_owned);
+        let json = m.get_flag("json");
+        if json && player.is_some() {
+            return Err(format!(
+                "'{CYAN_HIGH_INTENSITY_BOLD}--json{RESET}' cannot be used with '{CYAN_HIGH_INTENSITY_BOLD}-p{RESET}, {CYAN_HIGH_INTENSITY_BOLD}--player <MEDIA-PLAYER>{RESET}'"
+            ));
+        }
+
+        let mut urls = m.get_many("url").to_vec();
+        let catalog_sync = catalog_sync(&mut urls)?;
+        let watch = watch(&m, &mut urls)?;
+        let search = search(&m, &mut urls)?;
 
         Ok(Self {
-            urls: m.get_many("url").to_vec(),
+            urls,
             level_filter,
             lazy: m.get_flag("lazy"),
+            jobs,
             help: m.get_flag("help"),
-            player: m.get_one("player").map(str::to_owned),
+            player,
             quality,
+            json,
+            no_redact: m.get_flag("no_redact"),
             translation_title: m.get_one("translation_title").map(str::to_owned),
             translation_type,
+            season,
             episode,
             cookie: m.get_one("cookie").map(str::to_owned),
+            cached_cookie: None,
+            kodik_token: m.get_one("kodik_token").map(str::to_owned),
+            proxies: ProxyConfig {
+                all: m.get_one("proxy").map(str::to_owned),
+                kodik_api: m.get_one("proxy_kodik_api").map(str::to_owned),
+                shikimori: m.get_one("proxy_shikimori").map(str::to_owned),
+                player: m.get_one("proxy_player").map(str::to_owned),
+            },
+            resolve,
+            dns,
+            search,
+            catalog_sync,
+            watch,
         })
     }
 }
+
+/// Formats the error of an invalid value given to `flag`.
+fn invalid(flag: &str, e: &kodik_utils::Error) -> String {
+    format!(
+        "'{CYAN_HIGH_INTENSITY_BOLD}{flag}{RESET}': {e}\n
+For more information, try '{CYAN_HIGH_INTENSITY_BOLD}--help{RESET}'."
+    )
+}
+
+/// Takes `catalog sync` out of `urls`.
+fn catalog_sync(urls: &mut Vec<String>) -> Result<bool, String> {
+    if urls.first().is_none_or(|first| first != "catalog") {
+        return Ok(false);
+    }
+    match urls.get(1).map(String::as_str) {
+        Some("sync") if urls.len() == 2 => {
+            urls.clear();
+            Ok(true)
+        }
+        Some("search") => Ok(false),
+        _ => Err(format!(
+            "expected '{CYAN_HIGH_INTENSITY_BOLD}kodik catalog sync{RESET}' or '{CYAN_HIGH_INTENSITY_BOLD}kodik catalog search <TITLE>{RESET}'"
+        )),
+    }
+}
+
+/// Takes the tracks out of `urls` if the first one is `watch`.
+fn watch(m: &ArgMatches, urls: &mut Vec<String>) -> Result<Option<Watch>, String> {
+    let interval = m
+        .get_one("interval")
+        .map(|src| {
+            u64::from_str(src)
+                .ok()
+                .filter(|minutes| *minutes > 0)
+                .map(Duration::from_mins)
+                .ok_or_else(|| {
+                    format!(
+                "invalid value '{YELLOW_BOLD}{src}{RESET}' for '{CYAN_HIGH_INTENSITY_BOLD}--interval <MINUTES>{RESET}'\n
+For more information, try '{CYAN_HIGH_INTENSITY_BOLD}--help{RESET}'."
+                    )
+                })
+        })
+        .transpose()?;
+    let hook = m.get_one("hook").map(str::to_owned);
+
+    if urls.first().is_none_or(|first| first != "watch") {
+        if interval.is_some() || hook.is_some() {
+            return Err(format!(
+                "'{CYAN_HIGH_INTENSITY_BOLD}--interval{RESET}' and '{CYAN_HIGH_INTENSITY_BOLD}--hook{RESET}' can only be used with '{CYAN_HIGH_INTENSITY_BOLD}kodik watch <ID>...{RESET}'"
+            ));
+        }
+        return Ok(None);
+    }
+
+    let tracks = urls
+        .drain(..)
+        .skip(1)
+        .map(|src| {
+            if crate::is_shikimori(&src) {
+                kodik_shiki::extract_id(&src)
+                    .map(|id| Track::from(ExternalId::Shikimori(id.to_owned())))
+            } else {
+                src.parse()
+            }
+            .map_err(|e| invalid("<ID>", &e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if tracks.is_empty() {
+        return Err(format!(
+            "at least one id is required for '{CYAN_HIGH_INTENSITY_BOLD}kodik watch <ID>...{RESET}'"
+        ));
+    }
+
+    Ok(Some(Watch {
+        tracks,
+        interval,
+        hook,
+    }))
+}
+
+/// Takes the search title out of `urls` if the first one is `search` or
+/// `catalog search`.
+fn search(m: &ArgMatches, urls: &mut Vec<String>) -> Result<Option<Search>, String> {
+    let years = m
+        .get_many("year")
+        .iter()
+        .map(|src| {
+            src.parse::<u16>().map_err(|_| {
+                format!(
+                "invalid value '{YELLOW_BOLD}{src}{RESET}' for '{CYAN_HIGH_INTENSITY_BOLD}--year <YEAR>{RESET}'\n
+For more information, try '{CYAN_HIGH_INTENSITY_BOLD}--help{RESET}'."
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let pick = m
+        .get_one("pick")
+        .map(|src| {
+            usize::from_str(src).ok().filter(|n| *n > 0).ok_or_else(|| {
+                format!(
+                "invalid value '{YELLOW_BOLD}{src}{RESET}' for '{CYAN_HIGH_INTENSITY_BOLD}--pick <N>{RESET}'\n
+For more information, try '{CYAN_HIGH_INTENSITY_BOLD}--help{RESET}'."
+                )
+            })
+        })
+        .transpose()?;
+
+    let types = m
+        .get_many("material_type")
+        .iter()
+        .map(|src| {
+            MaterialType::from_name(&src.to_lowercase()).ok_or_else(|| {
+                let names = MaterialType::ALL.map(|t| format!("{CYAN_HIGH_INTENSITY_BOLD}{}{RESET}", t.as_str()));
+                format!(
+                "invalid value '{YELLOW_BOLD}{src}{RESET}' for '{CYAN_HIGH_INTENSITY_BOLD}--material-type <TYPE>{RESET}'
+  [possible values: {}]\n
+For more information, try '{CYAN_HIGH_INTENSITY_BOLD}--help{RESET}'.",
+                    names.join(", ")
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let genres = m.get_many("genre").to_vec();
+
+    let command = (
+        urls.first().map(String::as_str),
+        urls.get(1).map(String::as_str),
+    );
+    let offline = match command {
+        (Some("search"), _) => false,
+        (Some("catalog"), Some("search")) => true,
+        _ => {
+            if !years.is_empty() || !genres.is_empty() || !types.is_empty() || pick.is_some() {
+                return Err(format!(
+                    "'{CYAN_HIGH_INTENSITY_BOLD}--year{RESET}', '{CYAN_HIGH_INTENSITY_BOLD}--genre{RESET}', '{CYAN_HIGH_INTENSITY_BOLD}--material-type{RESET}' and '{CYAN_HIGH_INTENSITY_BOLD}--pick{RESET}' can only be used with '{CYAN_HIGH_INTENSITY_BOLD}kodik search <TITLE>{RESET}'"
+                ));
+            }
+            return Ok(None);
+        }
+    };
+
+    let title = urls.split_off(if offline { 2 } else { 1 }).join(" ");
+    urls.clear();
+    if title.trim().is_empty() {
+        return Err(format!(
+            "a title is required for '{CYAN_HIGH_INTENSITY_BOLD}kodik search <TITLE>{RESET}'"
+        ));
+    }
+
+    Ok(Some(Search {
+        title,
+        years,
+        types,
+        genres,
+        offline,
+        pick,
+    }))
+}
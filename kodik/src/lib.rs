@@ -1,14 +1,27 @@
-use crate::cache::Cache;
+use crate::cache::{COOKIES_PATH, Cache};
 use crate::config::{COMMAND, Config, Quality};
-use kodik_parser::{Response, reqwest::Client};
+use crate::settings::Settings;
+use futures_util::StreamExt as _;
+use kodik_api::{Material, catalog::Catalog};
+use kodik_parser::{
+    Response,
+    reqwest::{Client, Url},
+    schema::Video,
+};
+use kodik_shiki::ExternalId;
+use kodik_utils::cookies::CookieJar;
 use log::LevelFilter;
-use std::io::Write;
-use std::io::{self, BufWriter};
+use std::io::{self, Write};
+use std::pin::pin;
 use std::process::{Command, ExitCode, Stdio};
 
 mod cache;
+mod catalog;
 mod config;
 mod logging;
+mod search;
+mod settings;
+mod watch;
 
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used, clippy::indexing_slicing)]
@@ -23,6 +36,7 @@ pub async fn run(args: Vec<String>) -> ExitCode {
 
     let mut config = match Config::build(args) {
         Ok(config) => {
+            kodik_utils::redact::set_enabled(!config.no_redact);
             logging::setup_logging(config.level_filter);
             config
         }
@@ -43,106 +57,288 @@ pub async fn run(args: Vec<String>) -> ExitCode {
         cache.apply(&mut config);
     }
 
-    let client = Client::new();
+    let settings = match Settings::load() {
+        Ok(settings) => settings,
+        Err(e) => {
+            log::error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    kodik_utils::RATE_LIMITER.set_limits(settings.rate_limits());
+    if let Some(profile) = settings.browser_profile() {
+        kodik_utils::profile::set_session_profile(profile);
+    }
+
+    let jar = load_cookie_jar();
+    let client = match settings
+        .client(&mut config)
+        .and_then(|builder| builder.cookie_jar(&jar).build())
+    {
+        Ok(client) => client,
+        Err(e) => {
+            log::error!("{}", report(&e));
+            return ExitCode::FAILURE;
+        }
+    };
     let use_lazy = config.lazy || config.player.is_some();
 
+    let kodik_token = settings.kodik_token(&mut config);
+    if config.catalog_sync {
+        return catalog::sync(&client, kodik_token.as_deref()).await;
+    }
+    if let Some(watch) = config.watch.take() {
+        return watch::run(&client, kodik_token.as_deref(), &watch, config.json).await;
+    }
+
+    let catalog = catalog::open();
+
+    if let Some(search) = config.search.take() {
+        let token = kodik_token.as_deref();
+        if let Err(e) = search::run(&client, token, catalog.as_ref(), &search, &mut config).await {
+            log::error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    }
+    if let Err(e) = expand_urls(
+        &client,
+        &jar,
+        kodik_token.as_deref(),
+        catalog.as_ref(),
+        &mut config,
+    )
+    .await
+    {
+        log::error!("{}", report(&e));
+        return ExitCode::FAILURE;
+    }
+
+    let output = Output {
+        quality: config.quality,
+        json: config.json,
+    };
+
+    let exit_code = if use_lazy {
+        run_lazy(&client, config.urls, output, config.player).await
+    } else {
+        run_parallel(&client, config.urls, output, config.jobs).await
+    };
+
+    if let Some(cache) = cache_opt.as_mut()
+        && cache.is_changed(config.cached_cookie.as_deref())
+    {
+        log::warn!("Updating cache... in {}", cache.path.display());
+        cache.update(config.cached_cookie.as_deref());
+        cache.save();
+    }
+
+    if let Some(path) = COOKIES_PATH.as_ref()
+        && let Err(e) = jar.save(path)
+    {
+        log::warn!("{}", report(&e));
+    }
+
+    exit_code
+}
+
+/// Replaces every Shikimori URL and external id, such as `kp:326`, with the
+/// Kodik URLs of its episodes or film. Materials are looked up in the local
+/// catalog first, then with the Kodik API.
+async fn expand_urls(
+    client: &Client,
+    jar: &CookieJar,
+    kodik_token: Option<&str>,
+    catalog: Option<&Catalog>,
+    config: &mut Config,
+) -> Result<(), kodik_utils::Error> {
+    let mut token = None;
+    let selection = kodik_shiki::Selection {
+        translation_title: config.translation_title.as_deref(),
+        translation_type: config.translation_type.0.as_ref(),
+        season: config.season,
+        episodes: config.episode.as_ref(),
+    };
+
     let mut idx = 0;
     while idx < config.urls.len() {
         let Some(url) = config.urls.get(idx) else {
             break;
         };
 
-        if url.starts_with("https://shiki") {
-            match kodik_shiki::run(
-                &client,
+        let result = if is_shikimori(url) {
+            let id = ExternalId::Shikimori(kodik_shiki::extract_id(url)?.to_owned());
+            let materials = find_materials(client, catalog, kodik_token, &mut token, &id).await?;
+            let cookie = shikimori_cookie(
+                jar,
                 url,
                 config.cookie.as_deref(),
-                config.translation_title.as_deref(),
-                config.translation_type.0.as_ref(),
-                config.episode,
-            )
-            .await
-            {
-                Ok(video_result) => match video_result {
-                    kodik_shiki::VideoResult::Episodes(episodes) => {
-                        let episode_count = episodes.len();
-                        config.urls.splice(idx..=idx, episodes);
-                        idx += episode_count;
-                    }
-                    kodik_shiki::VideoResult::Film(film) => {
-                        if let Some(url_ref) = config.urls.get_mut(idx) {
-                            *url_ref = film;
-                        }
-                        idx += 1;
-                    }
-                },
-                Err(e) => {
-                    log::error!("{e}");
-                    return ExitCode::FAILURE;
-                }
-            }
+                &mut config.cached_cookie,
+            );
+            kodik_shiki::run_with(client, materials, url, cookie.as_deref(), &selection).await?
+        } else if let Ok(id) = url.parse::<ExternalId>() {
+            let materials = find_materials(client, catalog, kodik_token, &mut token, &id).await?;
+            kodik_shiki::lookup_with(materials, &selection)?
         } else {
             idx += 1;
+            continue;
+        };
+
+        match result {
+            kodik_shiki::VideoResult::Episodes(episodes) => {
+                let episode_count = episodes.len();
+                config
+                    .urls
+                    .splice(idx..=idx, episodes.into_iter().map(|ep| ep.link));
+                idx += episode_count;
+            }
+            kodik_shiki::VideoResult::Film(film) => {
+                if let Some(url_ref) = config.urls.get_mut(idx) {
+                    *url_ref = film;
+                }
+                idx += 1;
+            }
         }
     }
 
-    let exit_code = if use_lazy {
-        run_lazy(&client, config.urls, config.quality, config.player).await
-    } else {
-        run_parallel(&client, config.urls, config.quality).await
+    Ok(())
+}
+
+/// Returns the materials of `id` in the local `catalog` if it was synced
+/// recently, or else from the Kodik API. A stale catalog entry is only used
+/// if the API fails. The token is resolved into `token` the first time the
+/// API is needed.
+async fn find_materials(
+    client: &Client,
+    catalog: Option<&Catalog>,
+    kodik_token: Option<&str>,
+    token: &mut Option<String>,
+    id: &ExternalId,
+) -> Result<Vec<Material>, kodik_utils::Error> {
+    let cached = catalog.and_then(|catalog| match catalog.find(id) {
+        Ok(materials) if !materials.is_empty() => Some((materials, catalog::is_fresh(catalog))),
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("{}", report(&e));
+            None
+        }
+    });
+    if let Some((materials, true)) = cached {
+        log::info!("Found {id} in the local catalog");
+        return Ok(materials);
+    }
+
+    let fetched = match token {
+        Some(token) => Ok(token),
+        None => kodik_shiki::token::resolve(kodik_token).map(|new| token.insert(new)),
+    };
+    let fetched = match fetched {
+        Ok(token) => kodik_shiki::get_kodik_videos(client, token, id)
+            .await
+            .map(|page| page.results),
+        Err(e) => Err(e),
     };
+    match (fetched, cached) {
+        (Err(e), Some((materials, _))) => {
+            log::warn!("{}, using {id} from the stale local catalog", report(&e));
+            Ok(materials)
+        }
+        (fetched, _) => fetched,
+    }
+}
 
-    if let Some(cache) = cache_opt.as_mut()
-        && cache.is_changed(config.cookie.as_deref())
+fn is_shikimori(url: &str) -> bool {
+    url.starts_with("https://shiki")
+}
+
+fn load_cookie_jar() -> CookieJar {
+    COOKIES_PATH.as_ref().map_or_else(CookieJar::new, |path| {
+        CookieJar::load(path).unwrap_or_else(|e| {
+            log::warn!("{}", report(&e));
+            CookieJar::new()
+        })
+    })
+}
+
+/// Returns the cookies to send to the Shikimori site of `url`.
+///
+/// A `--cookie` given on the command line replaces the stored cookies, a
+/// cookie left in the cache by older versions only fills an empty jar.
+/// Cookies refreshed by Shikimori end up in the jar and are saved with it.
+fn shikimori_cookie(
+    jar: &CookieJar,
+    url: &str,
+    cookie: Option<&str>,
+    cached_cookie: &mut Option<String>,
+) -> Option<String> {
+    let site = Url::parse(url).and_then(|url| url.join("/")).ok()?;
+
+    if let Some(cookie) = cookie {
+        jar.add_header(cookie, &site);
+    } else if jar.header(&site).is_none()
+        && let Some(cookie) = cached_cookie.take()
     {
-        log::warn!("Updating cache... in {}", cache.path.display());
-        cache.update(config.cookie.as_deref());
-        cache.save();
+        jar.add_header(&cookie, &site);
     }
 
-    exit_code
+    jar.header(&site)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Output {
+    quality: Quality,
+    json: bool,
 }
 
-async fn run_parallel(client: &Client, urls: Vec<String>, quality: Quality) -> ExitCode {
-    let results = {
-        let mut set = tokio::task::JoinSet::new();
-        for (idx, url) in urls.into_iter().enumerate() {
-            let client = client.clone();
-            set.spawn(async move {
-                let result = kodik_parser::parse(&client, &url).await;
-                (idx, result)
-            });
+impl Output {
+    fn render(self, url: &str, response: &Response) -> Result<String, String> {
+        if self.json {
+            serde_json::to_string(&Video::new(url, response)).map_err(|e| e.to_string())
+        } else {
+            get_link(response, self.quality)
+                .map(ToOwned::to_owned)
+                .ok_or_else(|| "no playable links found for this video".to_owned())
         }
+    }
+}
 
-        let mut results = set.join_all().await;
-        results.sort_by_key(|a| a.0);
-        results
-    };
+async fn run_parallel(client: &Client, urls: Vec<String>, output: Output, jobs: usize) -> ExitCode {
+    let results = kodik_parser::parse_many(client, urls.iter().map(String::as_str), jobs);
+    let mut results = pin!(results);
 
-    let stdout = io::stdout();
-    let mut handle = BufWriter::new(stdout.lock());
+    let mut stdout = io::stdout();
+
+    for url in &urls {
+        let Some(res) = results.next().await else {
+            break;
+        };
 
-    for (_, res) in results {
         let kodik_response = match res {
             Ok(r) => r,
+            Err(e) if e.is_unavailable() => {
+                log::warn!("Skipping {url}: {e}");
+                continue;
+            }
             Err(e) => {
-                log::error!("{e}");
+                log::error!("{}", report(&*e));
                 return ExitCode::FAILURE;
             }
         };
 
-        let Some(link) = get_link(&kodik_response, quality) else {
-            log::error!("no playable links found for this video");
-            return ExitCode::FAILURE;
+        let line = match output.render(url, &kodik_response) {
+            Ok(line) => line,
+            Err(e) => {
+                log::error!("{e}");
+                return ExitCode::FAILURE;
+            }
         };
 
-        if let Err(e) = writeln!(handle, "{link}") {
+        if let Err(e) = writeln!(stdout, "{line}") {
             log::error!("{e}");
             return ExitCode::FAILURE;
         }
     }
 
-    if let Err(e) = handle.flush() {
+    if let Err(e) = stdout.flush() {
         log::error!("{e}");
         return ExitCode::FAILURE;
     }
@@ -153,29 +349,44 @@ async fn run_parallel(client: &Client, urls: Vec<String>, quality: Quality) -> E
 async fn run_lazy(
     client: &Client,
     urls: Vec<String>,
-    quality: Quality,
+    output: Output,
     player: Option<String>,
 ) -> ExitCode {
     for url in urls {
         let kodik_response = match kodik_parser::parse(client, &url).await {
             Ok(r) => r,
+            Err(e) if e.is_unavailable() => {
+                log::warn!("Skipping {url}: {e}");
+                continue;
+            }
             Err(e) => {
-                log::error!("{e}");
+                log::error!("{}", report(&e));
                 return ExitCode::FAILURE;
             }
         };
 
-        let Some(link) = get_link(&kodik_response, quality) else {
-            log::error!("no playable links found for this video");
-            return ExitCode::FAILURE;
-        };
-
         if let Some(player) = &player {
+            let Some(link) = get_link(&kodik_response, output.quality) else {
+                log::error!("no playable links found for this video");
+                return ExitCode::FAILURE;
+            };
+
             if let Err(e) = spawn_player(player, link) {
                 log::error!("{e}");
                 return ExitCode::FAILURE;
             }
-        } else if let Err(e) = writeln!(io::stdout(), "{link}") {
+            continue;
+        }
+
+        let line = match output.render(&url, &kodik_response) {
+            Ok(line) => line,
+            Err(e) => {
+                log::error!("{e}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+        if let Err(e) = writeln!(io::stdout(), "{line}") {
             log::error!("{e}");
             return ExitCode::FAILURE;
         }
@@ -203,6 +414,18 @@ fn get_link(response: &Response, quality: Quality) -> Option<&str> {
     }
 }
 
+/// Formats an error together with its chain of sources.
+fn report(e: &(dyn std::error::Error + 'static)) -> String {
+    let mut report = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        report.push_str(": ");
+        report.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    report
+}
+
 fn spawn_player(player: &str, link: &str) -> Result<(), String> {
     let mut parts = player.split_whitespace();
     let mut program = parts.next().ok_or("empty player")?;
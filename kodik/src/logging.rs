@@ -1,3 +1,4 @@
+use kodik_utils::redact::Redacted;
 use log::{Level, LevelFilter, Log, Metadata, Record};
 
 pub const CYAN: &str = "\x1b[0;36m";
@@ -7,7 +8,7 @@ pub const YELLOW_BOLD: &str = "\x1b[1;33m";
 pub const RED_BOLD: &str = "\x1b[1;31m";
 pub const RESET: &str = "\x1b[0m";
 const BLUE_BOLD: &str = "\x1b[1;34m";
-const BOLD: &str = "\x1b[1m";
+pub const BOLD: &str = "\x1b[1m";
 const DIM: &str = "\x1b[2m";
 
 struct KodikLogger;
@@ -26,15 +27,13 @@ impl Log for KodikLogger {
             return;
         }
 
+        let args = Redacted(record.args());
         match record.level() {
-            Level::Error => eprintln!("{RED_BOLD}error:{RESET} {BOLD}{}{RESET}", record.args()),
-            Level::Warn => eprintln!(
-                "{YELLOW_BOLD}warning:{RESET} {BOLD}{}{RESET}",
-                record.args()
-            ),
-            Level::Info => eprintln!("{BLUE_BOLD}::{RESET} {BOLD}{}{RESET}", record.args()),
-            Level::Debug => eprintln!("  {BLUE_BOLD}->{RESET} {}", record.args()),
-            Level::Trace => eprintln!("{DIM}{}{RESET}", record.args()),
+            Level::Error => eprintln!("{RED_BOLD}error:{RESET} {BOLD}{args}{RESET}"),
+            Level::Warn => eprintln!("{YELLOW_BOLD}warning:{RESET} {BOLD}{args}{RESET}"),
+            Level::Info => eprintln!("{BLUE_BOLD}::{RESET} {BOLD}{args}{RESET}"),
+            Level::Debug => eprintln!("  {BLUE_BOLD}->{RESET} {args}"),
+            Level::Trace => eprintln!("{DIM}{args}{RESET}"),
         }
     }
 
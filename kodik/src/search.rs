@@ -0,0 +1,192 @@
+//! `kodik search`: finding materials by title and picking one to play.
+use std::{
+    fmt::Write as _,
+    io::{self, IsTerminal, Write},
+};
+
+use kodik_api::{
+    ExternalId, Filters, KodikApi, Material, SearchRequest,
+    catalog::{Catalog, CatalogQuery},
+};
+use kodik_parser::reqwest::Client;
+use kodik_shiki::{Selection, VideoResult};
+
+use crate::{
+    config::{Config, Search},
+    logging::{BOLD, RESET},
+    report,
+};
+
+/// Searches Kodik, or the local `catalog` with `kodik catalog search`, for
+/// `search.title` and replaces the URLs of `config` with those of the
+/// material picked by `--pick`, by the user, or the only one found. A title
+/// such as `kp:326` is looked up by id instead.
+///
+/// Without a terminal to ask in, the results are printed to stdout instead
+/// and no URL is left.
+pub async fn run(
+    client: &Client,
+    kodik_token: Option<&str>,
+    catalog: Option<&Catalog>,
+    search: &Search,
+    config: &mut Config,
+) -> Result<(), String> {
+    let mut results = if search.offline {
+        let catalog = catalog.ok_or_else(|| {
+            format!("no local catalog, run '{BOLD}kodik catalog sync{RESET}' first")
+        })?;
+        catalog
+            .search(&catalog_query(search, config))
+            .map_err(|e| report(&e))?
+    } else {
+        let token = kodik_shiki::token::resolve(kodik_token).map_err(|e| report(&e))?;
+        KodikApi::new(client.clone(), token)
+            .search(&search_request(search, config))
+            .await
+            .map_err(|e| report(&e))?
+            .results
+    };
+    if let Some(title) = config.translation_title.as_deref() {
+        let title = title.to_lowercase();
+        results.retain(|material| material.translation.title.to_lowercase().contains(&title));
+    }
+
+    log::info!("Found {} result(s) for '{}'", results.len(), search.title);
+
+    let idx = match (search.pick, results.len()) {
+        (_, 0) => return Err(format!("nothing found for '{}'", search.title)),
+        (Some(pick), len) if pick > len => {
+            return Err(format!("cannot pick result {pick}, only {len} found"));
+        }
+        (Some(pick), _) => pick - 1,
+        (None, 1) => 0,
+        (None, _) if io::stdin().is_terminal() && io::stderr().is_terminal() => ask(&results)?,
+        (None, _) => {
+            let mut stdout = io::stdout();
+            for (idx, material) in results.iter().enumerate() {
+                writeln!(stdout, "{}", describe(idx + 1, material)).map_err(|e| e.to_string())?;
+            }
+            config.urls.clear();
+            return Ok(());
+        }
+    };
+
+    let material = results.swap_remove(idx);
+    log::info!("Picked {}", describe(idx + 1, &material));
+
+    let selection = Selection {
+        season: config.season,
+        episodes: config.episode.as_ref(),
+        ..Selection::default()
+    };
+    config.urls =
+        match kodik_shiki::lookup_with(vec![material], &selection).map_err(|e| report(&e))? {
+            VideoResult::Episodes(episodes) => episodes.into_iter().map(|ep| ep.link).collect(),
+            VideoResult::Film(film) => vec![film],
+        };
+    Ok(())
+}
+
+fn search_request(search: &Search, config: &Config) -> SearchRequest {
+    let request = search.title.parse::<ExternalId>().map_or_else(
+        |_| SearchRequest::title(&search.title),
+        |id| SearchRequest::external_id(&id),
+    );
+    SearchRequest {
+        with_episodes: true,
+        filters: Filters {
+            types: search.types.clone(),
+            year: search.years.clone(),
+            translation_type: config.translation_type.0,
+            all_genres: search.genres.clone(),
+            ..Filters::default()
+        },
+        ..request
+    }
+}
+
+/// Builds the catalog query of `kodik catalog search`, `--title` is applied
+/// afterwards like for online searches.
+pub fn catalog_query(search: &Search, config: &Config) -> CatalogQuery {
+    let (title, id) = search.title.parse::<ExternalId>().map_or_else(
+        |_| (Some(search.title.clone()), None),
+        |id| (None, Some(id)),
+    );
+    CatalogQuery {
+        title,
+        years: search.years.clone(),
+        types: search.types.clone(),
+        genres: search.genres.clone(),
+        translation_type: config.translation_type.0,
+        id,
+        ..CatalogQuery::default()
+    }
+}
+
+/// Lists `results` on stderr and asks for the 0-based index of one of them.
+fn ask(results: &[Material]) -> Result<usize, String> {
+    let mut stderr = io::stderr();
+    for (idx, material) in results.iter().enumerate() {
+        let _ = writeln!(stderr, "{}", describe(idx + 1, material));
+    }
+
+    loop {
+        let _ = write!(stderr, "{BOLD}Pick a number (1-{}):{RESET} ", results.len());
+        let _ = stderr.flush();
+
+        let mut line = String::new();
+        if io::stdin()
+            .read_line(&mut line)
+            .map_err(|e| e.to_string())?
+            == 0
+        {
+            return Err("nothing picked".to_owned());
+        }
+        match line.trim().parse::<usize>() {
+            Ok(n) if (1..=results.len()).contains(&n) => return Ok(n - 1),
+            _ => log::warn!(
+                "'{}' is not a number from 1 to {}",
+                line.trim(),
+                results.len()
+            ),
+        }
+    }
+}
+
+/// Formats a search result as one line: index, titles, year, type,
+/// translation, episodes and quality.
+pub fn describe(index: usize, material: &Material) -> String {
+    let mut line = format!("{index:>3}. {}", material.title);
+    if let Some(title_orig) = material
+        .title_orig
+        .as_deref()
+        .filter(|orig| !orig.is_empty() && *orig != material.title)
+    {
+        line.push_str(" / ");
+        line.push_str(title_orig);
+    }
+    if let Some(year) = material.year {
+        let _ = write!(line, " ({year})");
+    }
+    let _ = write!(
+        line,
+        " [{}] {}, {}",
+        material.r#type.as_str(),
+        material.translation.title,
+        material.translation.r#type.as_str()
+    );
+    match (material.last_episode, material.episodes_count) {
+        (Some(last), Some(count)) if last != count => {
+            let _ = write!(line, ", {last}/{count} episodes");
+        }
+        (_, Some(count)) | (Some(count), None) => {
+            let _ = write!(line, ", {count} episodes");
+        }
+        (None, None) => {}
+    }
+    if let Some(quality) = material.quality.as_deref() {
+        line.push_str(", ");
+        line.push_str(quality);
+    }
+    line
+}
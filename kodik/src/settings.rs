@@ -0,0 +1,156 @@
+use std::{fs, io, mem, path::PathBuf, sync::LazyLock};
+
+use kodik_utils::{
+    Error,
+    dns::parse_nameserver,
+    http::{ClientBuilder, ProxyConfig, parse_resolve},
+    limit,
+    profile::BrowserProfile,
+};
+use serde::Deserialize;
+
+use crate::config::Config;
+
+pub static SETTINGS_PATH: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
+    dirs::config_dir().map(|config_dir| config_dir.join("kodik").join("config.json"))
+});
+
+/// Settings from the config file, overridden by command line options.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub proxy: Option<String>,
+    pub proxies: Proxies,
+    /// Address overrides as `HOST:ADDR`, like `--resolve`.
+    pub resolve: Vec<String>,
+    /// DNS servers, like `--dns`.
+    pub dns: Vec<String>,
+    /// Request limits replacing the built-in ones, by host group.
+    pub rate_limits: RateLimits,
+    /// Kodik API token, used when neither `--kodik-token` nor `KODIK_TOKEN`
+    /// is given.
+    pub kodik_token: Option<String>,
+    /// Seed picking the browser profile sent with every request, so runs
+    /// look like the same browser. A new profile is picked each run if unset.
+    pub profile_seed: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Proxies {
+    pub kodik_api: Option<String>,
+    pub shikimori: Option<String>,
+    pub player: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimits {
+    pub kodik_api: Option<RateLimit>,
+    pub shikimori: Option<RateLimit>,
+    pub player: Option<RateLimit>,
+}
+
+/// Limit of a host group, either window may be `null` for no limit.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    pub per_second: Option<u32>,
+    pub per_minute: Option<u32>,
+}
+
+impl From<RateLimit> for limit::RateLimit {
+    fn from(limit: RateLimit) -> Self {
+        Self {
+            per_second: limit.per_second,
+            per_minute: limit.per_minute,
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the config file. A missing file gives the default settings.
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = SETTINGS_PATH.as_ref() else {
+            return Ok(Self::default());
+        };
+
+        match fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content)
+                .map_err(|e| format!("invalid config file {}: {e}", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("cannot read config file {}: {e}", path.display())),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(content)
+    }
+
+    pub fn proxies(&self) -> ProxyConfig {
+        ProxyConfig {
+            all: self.proxy.clone(),
+            kodik_api: self.proxies.kodik_api.clone(),
+            shikimori: self.proxies.shikimori.clone(),
+            player: self.proxies.player.clone(),
+        }
+    }
+
+    /// Returns the built-in rate limits with the ones from the file applied.
+    pub fn rate_limits(&self) -> limit::RateLimits {
+        let defaults = limit::RateLimits::default();
+        let or = |limit: Option<RateLimit>, default| limit.map_or(default, Into::into);
+        limit::RateLimits {
+            kodik_api: or(self.rate_limits.kodik_api, defaults.kodik_api),
+            shikimori: or(self.rate_limits.shikimori, defaults.shikimori),
+            player: or(self.rate_limits.player, defaults.player),
+        }
+    }
+
+    /// Returns the browser profile picked by `profile_seed`, if set.
+    pub fn browser_profile(&self) -> Option<BrowserProfile> {
+        self.profile_seed.map(BrowserProfile::from_seed)
+    }
+
+    /// Returns the Kodik API token from `--kodik-token`, the `KODIK_TOKEN`
+    /// environment variable or the file, in that order.
+    pub fn kodik_token(&self, config: &mut Config) -> Option<String> {
+        config
+            .kodik_token
+            .take()
+            .or_else(kodik_shiki::token::from_env)
+            .or_else(|| self.kodik_token.clone())
+    }
+
+    /// Configures the HTTP client from the command line options, falling back
+    /// to the settings for everything not given there.
+    ///
+    /// Address overrides are merged per host, the command line winning. DNS
+    /// servers given on the command line replace the ones from the file.
+    pub fn client(&self, config: &mut Config) -> Result<ClientBuilder, Error> {
+        let mut builder =
+            ClientBuilder::new().proxies(mem::take(&mut config.proxies).or(self.proxies()));
+
+        let cli_resolve = mem::take(&mut config.resolve);
+        let mut file_resolve = self
+            .resolve
+            .iter()
+            .map(|entry| parse_resolve(entry))
+            .collect::<Result<Vec<_>, _>>()?;
+        file_resolve.retain(|(host, _)| !cli_resolve.iter().any(|(cli_host, _)| cli_host == host));
+        for (host, addr) in cli_resolve.into_iter().chain(file_resolve) {
+            builder = builder.resolve(&host, addr);
+        }
+
+        let nameservers = if config.dns.is_empty() {
+            self.dns
+                .iter()
+                .map(|server| parse_nameserver(server))
+                .collect::<Result<_, _>>()?
+        } else {
+            mem::take(&mut config.dns)
+        };
+
+        Ok(builder.nameservers(nameservers))
+    }
+}
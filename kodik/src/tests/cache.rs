@@ -1,6 +1,6 @@
-use std::fs::OpenOptions;
+use std::{collections::BTreeMap, fs::OpenOptions, path::PathBuf};
 
-use kodik_parser::KODIK_STATE;
+use kodik_parser::{DomainState, KODIK_STATE};
 
 use crate::{
     cache::{CACHE_PATH, Cache},
@@ -15,6 +15,8 @@ fn load_test() -> Cache {
         cache = Cache {
             shift: Some(13),
             endpoint: Some(String::from("/abcd")),
+            fingerprint: None,
+            domains: BTreeMap::new(),
             cookie: None,
             path: CACHE_PATH.as_ref().unwrap().to_owned(),
         };
@@ -34,3 +36,31 @@ fn apply_test() {
     assert!(!KODIK_STATE.endpoint().is_empty());
     assert_ne!(KODIK_STATE.shift(), 0);
 }
+
+#[test]
+fn seen_at_alone_is_not_a_change() {
+    let domain = |fingerprint: &str, seen_at| DomainState {
+        fingerprint: Some(fingerprint.to_owned()),
+        seen_at,
+    };
+    let cache = Cache {
+        shift: Some(13),
+        endpoint: Some(String::from("/abcd")),
+        fingerprint: Some(String::from("v1")),
+        domains: BTreeMap::from([("kodik.info".to_owned(), domain("v1", 100))]),
+        cookie: None,
+        path: PathBuf::new(),
+    };
+
+    let mut snapshot = cache.snapshot();
+    snapshot.domains = BTreeMap::from([("kodik.info".to_owned(), domain("v1", 200))]);
+    assert!(!cache.differs_from(&snapshot, None));
+
+    snapshot.domains = BTreeMap::from([("kodik.info".to_owned(), domain("v2", 200))]);
+    assert!(cache.differs_from(&snapshot, None));
+
+    let mut snapshot = cache.snapshot();
+    snapshot.shift = 14;
+    assert!(cache.differs_from(&snapshot, None));
+    assert!(cache.differs_from(&cache.snapshot(), Some("cookie")));
+}
@@ -0,0 +1,51 @@
+use kodik_shiki::SeasonSelector;
+
+use crate::config::Config;
+
+fn args(args: &[&str]) -> Vec<String> {
+    std::iter::once("kodik")
+        .chain(args.iter().copied())
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn season_option() {
+    let url = "https://shikimori.one/animes/z20-naruto";
+    let season = |value: &str| Config::build(args(&[url, "--season", value])).map(|c| c.season);
+
+    assert_eq!(
+        SeasonSelector::Latest,
+        Config::build(args(&[url])).unwrap().season
+    );
+    assert_eq!(SeasonSelector::All, season("all").unwrap());
+    assert_eq!(SeasonSelector::Number(0), season("0").unwrap());
+    assert!(season("-1").is_err());
+}
+
+#[test]
+fn episode_option() {
+    let url = "https://shikimori.one/animes/z20-naruto";
+    let episode = |value: &str| {
+        Config::build(args(&[url, "-e", value])).map(|c| c.episode.map(|e| e.to_string()))
+    };
+
+    assert!(Config::build(args(&[url])).unwrap().episode.is_none());
+    assert_eq!(Some("1,4,9-12".to_owned()), episode("1,4,9-12").unwrap());
+    assert_eq!(Some("5-".to_owned()), episode("5-").unwrap());
+    assert_eq!(Some("last".to_owned()), episode("last").unwrap());
+    assert!(episode("7-3").is_err());
+}
+
+#[test]
+fn json_conflicts_with_player() {
+    let url = "https://kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p";
+
+    assert!(Config::build(args(&[url, "--json", "--player", "mpv"])).is_err());
+    assert!(Config::build(args(&[url, "-p", "mpv"])).is_ok());
+    assert!(
+        Config::build(args(&[url, "--json", "--lazy"]))
+            .unwrap()
+            .json
+    );
+}
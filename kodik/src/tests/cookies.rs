@@ -0,0 +1,46 @@
+use kodik_parser::reqwest::Url;
+use kodik_utils::cookies::CookieJar;
+
+use crate::shikimori_cookie;
+
+const URL: &str = "https://shikimori.one/animes/z20-naruto";
+
+#[test]
+fn command_line_cookie_replaces_stored_one() {
+    let jar = CookieJar::new();
+    let site = Url::parse("https://shikimori.one/").unwrap();
+    jar.add_header("_kawai_session=old", &site);
+
+    let mut cached = None;
+    let cookie = shikimori_cookie(&jar, URL, Some("_kawai_session=new"), &mut cached);
+
+    assert_eq!(Some("_kawai_session=new"), cookie.as_deref());
+    assert_eq!(
+        Some("new".to_owned()),
+        jar.get(
+            &Url::parse("https://shikimori.one/api/animes/20").unwrap(),
+            "_kawai_session"
+        )
+    );
+}
+
+#[test]
+fn cached_cookie_only_fills_empty_jar() {
+    let jar = CookieJar::new();
+    let mut cached = Some("_kawai_session=cached".to_owned());
+
+    let cookie = shikimori_cookie(&jar, URL, None, &mut cached);
+    assert_eq!(Some("_kawai_session=cached"), cookie.as_deref());
+    assert_eq!(None, cached);
+
+    jar.add_header(
+        "_kawai_session=refreshed",
+        &Url::parse("https://shikimori.one/").unwrap(),
+    );
+    let mut cached = Some("_kawai_session=cached".to_owned());
+    let cookie = shikimori_cookie(&jar, URL, None, &mut cached);
+    assert_eq!(Some("_kawai_session=refreshed"), cookie.as_deref());
+    assert!(cached.is_some());
+
+    assert_eq!(None, shikimori_cookie(&jar, "not a url", None, &mut None));
+}
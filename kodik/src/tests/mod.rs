@@ -1,3 +1,7 @@
 mod cache;
-mod logging;
 mod config;
+mod cookies;
+mod logging;
+mod search;
+mod settings;
+mod watch;
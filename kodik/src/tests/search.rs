@@ -0,0 +1,125 @@
+use kodik_api::{ExternalId, Material, MaterialType, TranslationType};
+
+use crate::{
+    config::Config,
+    search::{catalog_query, describe},
+};
+
+fn args(args: &[&str]) -> Vec<String> {
+    std::iter::once("kodik")
+        .chain(args.iter().copied())
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn search_mode() {
+    let config = Config::build(args(&[
+        "search",
+        "ghost",
+        "in",
+        "the",
+        "shell",
+        "--material-type",
+        "anime-serial",
+        "--type",
+        "voice",
+        "--year",
+        "2002",
+        "--pick",
+        "2",
+    ]))
+    .unwrap();
+
+    assert!(config.urls.is_empty());
+    assert_eq!(Some(TranslationType::Voice), config.translation_type.0);
+    let search = config.search.unwrap();
+    assert_eq!("ghost in the shell", search.title);
+    assert_eq!(vec![MaterialType::AnimeSerial], search.types);
+    assert_eq!(vec![2002], search.years);
+    assert_eq!(Some(2), search.pick);
+
+    assert!(Config::build(args(&["search"])).is_err());
+    assert!(Config::build(args(&["search", "x", "--pick", "0"])).is_err());
+    assert!(Config::build(args(&["search", "x", "--material-type", "cartoon"])).is_err());
+    // Material types are not translation types and vice versa.
+    assert!(Config::build(args(&["search", "x", "--type", "anime-serial"])).is_err());
+    assert!(Config::build(args(&["search", "x", "--material-type", "voice"])).is_err());
+}
+
+#[test]
+fn catalog_mode() {
+    let config = Config::build(args(&["catalog", "sync"])).unwrap();
+    assert!(config.catalog_sync);
+    assert!(config.urls.is_empty());
+    assert!(config.search.is_none());
+
+    let config = Config::build(args(&[
+        "catalog",
+        "search",
+        "naruto",
+        "--genre",
+        "Сёнэн",
+        "--year",
+        "2002",
+        "--type",
+        "voice",
+    ]))
+    .unwrap();
+    assert!(!config.catalog_sync);
+    assert!(config.urls.is_empty());
+    let search = config.search.as_ref().unwrap();
+    assert!(search.offline);
+    assert_eq!("naruto", search.title);
+
+    let query = catalog_query(search, &config);
+    assert_eq!(Some("naruto".to_owned()), query.title);
+    assert_eq!(vec!["Сёнэн".to_owned()], query.genres);
+    assert_eq!(vec![2002], query.years);
+    assert_eq!(Some(TranslationType::Voice), query.translation_type);
+    assert_eq!(None, query.id);
+
+    let config = Config::build(args(&["catalog", "search", "kp:326"])).unwrap();
+    let query = catalog_query(config.search.as_ref().unwrap(), &config);
+    assert_eq!(None, query.title);
+    assert_eq!(Some(ExternalId::Kinopoisk("326".to_owned())), query.id);
+
+    assert!(Config::build(args(&["catalog"])).is_err());
+    assert!(Config::build(args(&["catalog", "sync", "now"])).is_err());
+    assert!(Config::build(args(&["catalog", "search"])).is_err());
+    assert!(
+        !Config::build(args(&["search", "x"]))
+            .unwrap()
+            .search
+            .unwrap()
+            .offline
+    );
+}
+
+#[test]
+fn search_options_need_search() {
+    let url = "https://kodikplayer.com/video/91873/060cab655974d46835b3f4405807acc2/720p";
+    assert!(Config::build(args(&[url, "--year", "2002"])).is_err());
+    assert!(Config::build(args(&[url, "--material-type", "anime"])).is_err());
+    assert!(Config::build(args(&[url, "--genre", "drama"])).is_err());
+
+    let config = Config::build(args(&[url, "--type", "subtitles"])).unwrap();
+    assert!(config.search.is_none());
+    assert_eq!(vec![url.to_owned()], config.urls);
+}
+
+#[test]
+fn describe_test() {
+    let material: Material = serde_json::from_str(
+        r#"{"id": "serial-1", "type": "anime-serial", "link": "//kodik.info/serial/1/a/720p",
+            "title": "Призрак в доспехах", "title_orig": "Koukaku Kidoutai", "year": 2002,
+            "last_episode": 20, "episodes_count": 26, "quality": "BDRip 720p",
+            "translation": {"id": 1, "title": "MC Entertainment", "type": "voice"}}"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        "  3. Призрак в доспехах / Koukaku Kidoutai (2002) [anime-serial] MC Entertainment, voice, 20/26 episodes, BDRip 720p",
+        describe(3, &material)
+    );
+}
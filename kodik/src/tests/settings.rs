@@ -0,0 +1,143 @@
+use std::net::{IpAddr, SocketAddr};
+
+use kodik_utils::{limit::RateLimits, profile::BrowserProfile};
+
+use crate::{config::Config, settings::Settings};
+
+#[test]
+fn parsing_settings() {
+    let settings = Settings::parse(
+        r#"{"proxy": "socks5h://127.0.0.1:1080", "proxies": {"shikimori": "http://10.0.0.1:3128"}}"#,
+    )
+    .unwrap();
+
+    let proxies = settings.proxies();
+    assert_eq!(Some("socks5h://127.0.0.1:1080"), proxies.all.as_deref());
+    assert_eq!(Some("http://10.0.0.1:3128"), proxies.shikimori.as_deref());
+    assert_eq!(None, proxies.player);
+
+    assert!(Settings::parse("{}").unwrap().proxies().is_empty());
+    assert!(Settings::parse(r#"{"proxi": "http://x"}"#).is_err());
+}
+
+#[test]
+fn cli_proxies_override_settings() {
+    let args = [
+        "kodik",
+        "--proxy",
+        "http://cli:1",
+        "--proxy-player",
+        "http://cli:2",
+    ]
+    .map(str::to_owned)
+    .to_vec();
+    let config = Config::build(args).unwrap();
+    let settings =
+        Settings::parse(r#"{"proxy": "http://file:1", "proxies": {"kodik_api": "http://file:2"}}"#)
+            .unwrap();
+
+    let proxies = config.proxies.or(settings.proxies());
+
+    assert_eq!(Some("http://cli:1"), proxies.all.as_deref());
+    assert_eq!(Some("http://cli:2"), proxies.player.as_deref());
+    assert_eq!(Some("http://file:2"), proxies.kodik_api.as_deref());
+}
+
+#[test]
+fn repeated_resolve_and_dns_options() {
+    let args = [
+        "kodik",
+        "--resolve",
+        "kodik.info:203.0.113.7",
+        "--resolve",
+        "shikimori.one:[2001:db8::1]",
+        "--dns",
+        "1.1.1.1",
+        "--dns",
+        "9.9.9.9:5353",
+    ]
+    .map(str::to_owned)
+    .to_vec();
+    let config = Config::build(args).unwrap();
+
+    assert_eq!(
+        vec![
+            ("kodik.info".to_owned(), IpAddr::from([203, 0, 113, 7])),
+            ("shikimori.one".to_owned(), "2001:db8::1".parse().unwrap()),
+        ],
+        config.resolve
+    );
+    assert_eq!(
+        vec![
+            SocketAddr::from(([1, 1, 1, 1], 53)),
+            SocketAddr::from(([9, 9, 9, 9], 5353))
+        ],
+        config.dns
+    );
+
+    for args in [
+        ["kodik", "--resolve", "kodik.info"],
+        ["kodik", "--dns", "dns.google"],
+    ] {
+        assert!(Config::build(args.map(str::to_owned).to_vec()).is_err());
+    }
+}
+
+#[test]
+fn cli_resolve_overrides_settings() {
+    let args = ["kodik", "--resolve", "kodik.info:203.0.113.7"]
+        .map(str::to_owned)
+        .to_vec();
+    let mut config = Config::build(args).unwrap();
+    let settings = Settings::parse(
+        r#"{"resolve": ["kodik.info:198.51.100.1", "shikimori.one:198.51.100.2"], "dns": ["8.8.8.8"]}"#,
+    )
+    .unwrap();
+
+    assert!(settings.client(&mut config).unwrap().build().is_ok());
+    assert!(config.resolve.is_empty());
+
+    let invalid = Settings::parse(r#"{"resolve": ["kodik.info"]}"#).unwrap();
+    assert!(invalid.client(&mut config).is_err());
+}
+
+#[test]
+fn rate_limits_override_defaults() {
+    let settings =
+        Settings::parse(r#"{"rate_limits": {"shikimori": {"per_second": 2, "per_minute": null}}}"#)
+            .unwrap();
+    let limits = settings.rate_limits();
+    let defaults = RateLimits::default();
+
+    assert_eq!(Some(2), limits.shikimori.per_second);
+    assert_eq!(None, limits.shikimori.per_minute);
+    assert_eq!(defaults.player, limits.player);
+    assert_eq!(defaults, Settings::parse("{}").unwrap().rate_limits());
+    assert!(Settings::parse(r#"{"rate_limits": {"shikimori": {"per_hour": 1}}}"#).is_err());
+}
+
+#[test]
+fn profile_seed_picks_browser_profile() {
+    let settings = Settings::parse(r#"{"profile_seed": 42}"#).unwrap();
+
+    assert_eq!(
+        Some(BrowserProfile::from_seed(42)),
+        settings.browser_profile()
+    );
+    assert_eq!(None, Settings::parse("{}").unwrap().browser_profile());
+}
+
+#[test]
+fn cli_kodik_token_overrides_settings() {
+    let settings = Settings::parse(r#"{"kodik_token": "file"}"#).unwrap();
+
+    let args = ["kodik", "--kodik-token", "cli"]
+        .map(str::to_owned)
+        .to_vec();
+    let mut config = Config::build(args).unwrap();
+    assert_eq!(Some("cli"), settings.kodik_token(&mut config).as_deref());
+
+    let mut config = Config::build(["kodik"].map(str::to_owned).to_vec()).unwrap();
+    let expected = kodik_shiki::token::from_env().unwrap_or_else(|| "file".to_owned());
+    assert_eq!(Some(expected), settings.kodik_token(&mut config));
+}
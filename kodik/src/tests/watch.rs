@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use kodik_api::{ExternalId, NewEpisode, Translation, TranslationType};
+
+use crate::{config::Config, watch::describe};
+
+fn args(args: &[&str]) -> Vec<String> {
+    std::iter::once("kodik")
+        .chain(args.iter().copied())
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn watch_mode() {
+    let config = Config::build(args(&[
+        "watch",
+        "shiki:20@610",
+        "https://shikimori.one/animes/z21-one-piece",
+        "--interval",
+        "30",
+        "--hook",
+        "notify-send new",
+    ]))
+    .unwrap();
+
+    assert!(config.urls.is_empty());
+    let watch = config.watch.unwrap();
+    assert_eq!(2, watch.tracks.len());
+    assert_eq!(Some(610), watch.tracks[0].translation_id);
+    assert_eq!(ExternalId::Shikimori("21".to_owned()), watch.tracks[1].id);
+    assert_eq!(None, watch.tracks[1].translation_id);
+    assert_eq!(Some(Duration::from_mins(30)), watch.interval);
+    assert_eq!(Some("notify-send new"), watch.hook.as_deref());
+
+    let watch = Config::build(args(&["watch", "kp:326"]))
+        .unwrap()
+        .watch
+        .unwrap();
+    assert_eq!(None, watch.interval);
+
+    assert!(Config::build(args(&["watch"])).is_err());
+    assert!(Config::build(args(&["watch", "326"])).is_err());
+    assert!(Config::build(args(&["watch", "kp:326", "--interval", "0"])).is_err());
+    assert!(Config::build(args(&["kp:326", "--interval", "5"])).is_err());
+    assert!(Config::build(args(&["kp:326", "--hook", "true"])).is_err());
+}
+
+#[test]
+fn describe_test() {
+    let mut episode = NewEpisode {
+        track: "shiki:20".to_owned(),
+        material_id: "serial-1".to_owned(),
+        title: "Наруто".to_owned(),
+        translation: Translation {
+            id: 610,
+            title: "AniLibria.TV".to_owned(),
+            r#type: TranslationType::Voice,
+        },
+        season: 1,
+        episode: 221,
+        link: Some("//kodik.info/seria/1/abc/720p".to_owned()),
+    };
+    assert_eq!(
+        "Наруто (AniLibria.TV): season 1, episode 221 //kodik.info/seria/1/abc/720p",
+        describe(&episode)
+    );
+
+    episode.link = None;
+    assert_eq!(
+        "Наруто (AniLibria.TV): season 1, episode 221",
+        describe(&episode)
+    );
+}
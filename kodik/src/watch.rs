@@ -0,0 +1,148 @@
+//! `kodik watch`: reporting new episodes of tracked materials.
+use std::{
+    fs,
+    io::{self, Write},
+    process::{Command, ExitCode, Stdio},
+};
+
+use kodik_api::{KodikApi, NewEpisode, WatchState};
+use kodik_parser::reqwest::Client;
+
+use crate::{cache::WATCH_PATH, config::Watch, report};
+
+/// Polls the Kodik API for new episodes of `watch.tracks`, once or every
+/// `watch.interval`, and prints them, as JSON lines with `json`.
+pub async fn run(
+    client: &Client,
+    kodik_token: Option<&str>,
+    watch: &Watch,
+    json: bool,
+) -> ExitCode {
+    match try_run(client, kodik_token, watch, json).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            log::error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn try_run(
+    client: &Client,
+    kodik_token: Option<&str>,
+    watch: &Watch,
+    json: bool,
+) -> Result<(), String> {
+    let token = kodik_shiki::token::resolve(kodik_token).map_err(|e| report(&e))?;
+    let api = KodikApi::new(client.clone(), token);
+    let mut state = load();
+
+    loop {
+        match state.poll(&api, &watch.tracks).await {
+            Ok(episodes) => {
+                log::info!("{} new episode(s)", episodes.len());
+                for episode in &episodes {
+                    emit(episode, watch.hook.as_deref(), json)?;
+                }
+                save(&state);
+            }
+            // A failed poll is retried on the next one.
+            Err(e) if watch.interval.is_some() => log::warn!("{}", report(&e)),
+            Err(e) => return Err(report(&e)),
+        }
+
+        let Some(interval) = watch.interval else {
+            return Ok(());
+        };
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Prints `episode` to stdout and runs `hook` for it.
+fn emit(episode: &NewEpisode, hook: Option<&str>, json: bool) -> Result<(), String> {
+    let line = if json {
+        serde_json::to_string(episode).map_err(|e| e.to_string())?
+    } else {
+        describe(episode)
+    };
+    let mut stdout = io::stdout();
+    writeln!(stdout, "{line}")
+        .and_then(|()| stdout.flush())
+        .map_err(|e| e.to_string())?;
+
+    if let Some(hook) = hook
+        && let Err(e) = run_hook(hook, episode)
+    {
+        log::warn!("{e}");
+    }
+    Ok(())
+}
+
+/// Formats a new episode as one line: title, translation, season, episode
+/// and link.
+pub fn describe(episode: &NewEpisode) -> String {
+    let mut line = format!(
+        "{} ({}): season {}, episode {}",
+        episode.title, episode.translation.title, episode.season, episode.episode
+    );
+    if let Some(link) = episode.link.as_deref() {
+        line.push(' ');
+        line.push_str(link);
+    }
+    line
+}
+
+/// Runs `hook` with the new episode in `KODIK_*` environment variables.
+fn run_hook(hook: &str, episode: &NewEpisode) -> Result<(), String> {
+    let mut parts = hook.split_whitespace();
+    let program = parts.next().ok_or("empty hook")?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .env("KODIK_TRACK", &episode.track)
+        .env("KODIK_MATERIAL_ID", &episode.material_id)
+        .env("KODIK_TITLE", &episode.title)
+        .env("KODIK_TRANSLATION", &episode.translation.title)
+        .env("KODIK_TRANSLATION_ID", episode.translation.id.to_string())
+        .env("KODIK_SEASON", episode.season.to_string())
+        .env("KODIK_EPISODE", episode.episode.to_string())
+        .env("KODIK_LINK", episode.link.as_deref().unwrap_or_default())
+        .stdin(Stdio::null())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .map_err(|e| format!("failed to run hook '{program}': {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("hook '{program}' failed: {status}"))
+    }
+}
+
+/// Loads the state of the previous polls, or starts afresh.
+fn load() -> WatchState {
+    let Some(path) = WATCH_PATH.as_ref() else {
+        return WatchState::default();
+    };
+    let Ok(content) = fs::read_to_string(path) else {
+        return WatchState::default();
+    };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        log::warn!("Ignoring {}: {e}", path.display());
+        WatchState::default()
+    })
+}
+
+fn save(state: &WatchState) {
+    let Some(path) = WATCH_PATH.as_ref() else {
+        return;
+    };
+    let saved = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| serde_json::to_string(state).map_err(io::Error::other))
+        .and_then(|content| fs::write(path, content));
+    if let Err(e) = saved {
+        log::warn!("Failed to save {}: {e}", path.display());
+    }
+}